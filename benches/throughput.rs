@@ -0,0 +1,82 @@
+use chip8::{Chip8, asm, display::{Display, Pixels}, renderer};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A loop mixing arithmetic, skips, subroutine calls and draws, roughly what a game's main loop does
+const CPU_ROM: &str = "
+    LD I, sprite
+loop:
+    ADD V0, 1
+    LD V1, V0
+    SHR V1
+    XOR V2, V1
+    SE V0, 0xFF
+    CALL draw
+    JP loop
+
+draw:
+    DRW V0, V1, 5
+    RET
+
+sprite:
+    DB 0xF0, 0x90, 0xF0, 0x90, 0xF0
+";
+
+fn run_cycles(c: &mut Criterion) {
+    let rom = asm::assemble(CPU_ROM).unwrap();
+
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(rom).unwrap();
+
+    c.bench_function("run_cycles 10000", |b| b.iter(|| chip8.run_cycles(black_box(10_000))));
+}
+
+fn display_draw(c: &mut Criterion) {
+    let sprite = [0xFF; 15];
+    let mut display = Display::headless();
+
+    // Covers the whole screen with 8x15 sprites, toggling every pixel each iteration
+    c.bench_function("display draw full screen", |b| b.iter(|| {
+        for y in (0..Display::HEIGHT).step_by(15) {
+            for x in (0..Display::WIDTH).step_by(8) {
+                black_box(display.draw(x as u8, y as u8, &sprite));
+            }
+        }
+    }));
+}
+
+/// A quad per pixel as the screen was drawn before it became a texture, laid out like the old vertex buffer
+#[derive(Clone, Copy)]
+struct Instance {
+    _pos: [f32; 2],
+    _on: f32
+}
+
+/// The instance data the old renderer generated and uploaded every frame, one plane at the largest resolution
+fn gen_instances(pixels: &Pixels) -> [Instance; Display::MAX_WIDTH * Display::MAX_HEIGHT] {
+    let mut instances = [Instance { _pos: [0.0, 0.0], _on: 0.0 }; Display::MAX_WIDTH * Display::MAX_HEIGHT];
+
+    for y in 0..Display::MAX_HEIGHT {
+        for x in 0..Display::MAX_WIDTH {
+            instances[Display::MAX_WIDTH * y + x] = Instance {
+                _pos: [x as f32, y as f32],
+                _on: (pixels[y] >> (Display::MAX_WIDTH - 1 - x) & 1) as f32
+            };
+        }
+    }
+
+    instances
+}
+
+fn gen_texture_data(c: &mut Criterion) {
+    // Every other pixel on in the first plane and every third one in the second, so all four colors show up
+    let pixels: Pixels = [0xAAAA_AAAA_AAAA_AAAA_AAAA_AAAA_AAAA_AAAA; Display::MAX_HEIGHT];
+    let second_plane: Pixels = [0x9249_2492_4924_9249_2492_4924_9249_2492; Display::MAX_HEIGHT];
+
+    let mut group = c.benchmark_group("screen upload data");
+    group.bench_function("gen_texture_data", |b| b.iter(|| renderer::gen_texture_data(black_box(&pixels), black_box(&second_plane))));
+    group.bench_function("instanced quads", |b| b.iter(|| gen_instances(black_box(&pixels))));
+    group.finish();
+}
+
+criterion_group!(benches, run_cycles, display_draw, gen_texture_data);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+//! Runs a ROM built into the executable, so the emulator can be tried without a ROM file:
+//! `cargo run --example embedded_rom`
+//!
+//! It's also the smallest host for the library: a window, a 60Hz loop that runs a frame's worth of instructions and
+//! ticks the timers, keyboard input and rendering whenever the display changes
+
+use chip8::{Chip8, Chip8Config, chip8::FRAMES_PER_SECOND, display::Display};
+use std::time::{Duration, Instant};
+use winit::event::{ElementState, Event, KeyboardInput, WindowEvent};
+
+/// The IBM logo, the classic first ROM to get working
+const ROM: &[u8] = include_bytes!("ibm_logo.ch8");
+
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND as u64);
+
+fn main() {
+    let event_loop = winit::event_loop::EventLoop::new();
+
+    let window = winit::window::WindowBuilder::new()
+        .with_title("Chip 8 - Embedded ROM")
+        .with_inner_size(Display::window_size(10))
+        .build(&event_loop)
+        .unwrap();
+
+    let mut chip8 = Chip8::from_config(Chip8Config::default(), &window).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+
+    chip8.load_program(ROM.to_vec()).expect("the embedded ROM fits in memory");
+
+    let mut next_frame = Instant::now();
+
+    event_loop.run(move |event, _, control_flow| {
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => control_flow.set_exit(),
+                WindowEvent::KeyboardInput {
+                    input: KeyboardInput { virtual_keycode: Some(keycode), state, .. },
+                    ..
+                } => match state {
+                    ElementState::Pressed => chip8.on_key_down(&keycode),
+                    ElementState::Released => chip8.on_key_up(&keycode)
+                },
+                WindowEvent::Resized(new_size) => chip8.handle_resize(&new_size),
+                _ => {}
+            },
+            Event::MainEventsCleared => {
+                let now = Instant::now();
+
+                if now >= next_frame {
+                    if let Err(error) = chip8.run_cycles(chip8.cycles_per_frame()) {
+                        eprintln!("The ROM stopped: {error}");
+                        control_flow.set_exit();
+                    }
+
+                    chip8.tick_timers();
+                    // Frames missed while the loop was blocked are skipped rather than caught up on
+                    next_frame = (next_frame + FRAME_DURATION).max(now);
+
+                    if chip8.needs_redraw() {
+                        window.request_redraw();
+                    }
+                }
+
+                control_flow.set_wait_until(next_frame);
+            },
+            Event::RedrawRequested(_) => {
+                if let Err(error) = chip8.render() {
+                    eprintln!("Failed to render: {error}");
+                    control_flow.set_exit();
+                }
+            },
+            _ => ()
+        }
+    });
+}
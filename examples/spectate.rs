@@ -0,0 +1,24 @@
+//! Watches a game streamed by the emulator in the terminal. Start the emulator with `--spectate 0.0.0.0:8008`, then:
+//! `cargo run --example spectate --features net -- <host>:8008`
+
+use chip8::net::SpectatorClient;
+
+fn main() {
+    let address = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:8008".to_string());
+
+    let mut client = SpectatorClient::connect(&address).unwrap_or_else(|error| {
+        eprintln!("Couldn't connect to {address}: {error}");
+        std::process::exit(1);
+    });
+
+    loop {
+        match client.read_frame() {
+            // Clears the terminal and moves the cursor back to the top left before every frame
+            Ok(display) => print!("\x1B[2J\x1B[H{}", display.to_ascii()),
+            Err(error) => {
+                eprintln!("The stream ended: {error}");
+                break;
+            }
+        }
+    }
+}
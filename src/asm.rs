@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::instruction::Instruction;
+
+/// Where assembled programs are loaded, used to resolve label addresses
+const PROGRAM_START: u16 = crate::chip8::PROGRAM_START as u16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize, // 1 based line number of the offending source line
+    pub message: String
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles source written with the same mnemonics `disassemble` outputs into big endian opcode bytes.
+///
+/// Each line holds at most one instruction, optionally preceded by a `label:`. Comments start with `;`.
+/// Numbers can be decimal or hex (`0x` prefix) and anywhere an address is expected a label can be used instead.
+/// Raw data can be emitted with `DB byte, ...` and `DW word, ...`
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    let mut address = PROGRAM_START;
+
+    // First pass: find where every label points to
+    for (i, line) in source.lines().enumerate() {
+        let mut line = line.split(';').next().unwrap().trim();
+
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(AsmError { line: i + 1, message: format!("invalid label \"{label}\"") });
+            }
+
+            if labels.insert(label.to_uppercase(), address).is_some() {
+                return Err(AsmError { line: i + 1, message: format!("label \"{label}\" defined twice") });
+            }
+
+            line = rest.trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = split_operands(line);
+
+        address += match mnemonic.as_str() {
+            "DB" => operands.len() as u16,
+            "DW" => operands.len() as u16 * 2,
+            _ => 2
+        };
+
+        lines.push((i + 1, mnemonic, operands));
+    }
+
+    // Second pass: encode each line now that every label is known
+    let mut bytes = Vec::new();
+
+    for (line, mnemonic, operands) in lines {
+        let assembler = LineAssembler { line, labels: &labels };
+
+        match mnemonic.as_str() {
+            "DB" => {
+                for operand in &operands {
+                    bytes.push(assembler.number(operand, 0xFF)? as u8);
+                }
+            },
+            "DW" => {
+                for operand in &operands {
+                    bytes.extend_from_slice(&assembler.number(operand, 0xFFFF)?.to_be_bytes());
+                }
+            },
+            _ => bytes.extend_from_slice(&assembler.instruction(&mnemonic, &operands)?.to_be_bytes())
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Returns the mnemonic of an instruction with its operands, e.g. `LD V0, 0x05`.
+/// Opcodes that don't decode to an instruction are emitted as `DW` data so the output can be assembled back
+pub fn disassemble(instruction: &Instruction) -> String {
+    let x = instruction.x();
+    let y = instruction.y();
+    let nn = instruction.nn();
+    let nnn = instruction.nnn();
+
+    match (instruction.d1(), instruction.d2(), instruction.d3(), instruction.d4()) {
+        (0, 0, 0xE, 0) => "CLS".to_string(),
+        (0, 0, 0xE, 0xE) => "RET".to_string(),
+        (0, 0, 0xC, n) => format!("SCD {n:#03X}"),
+        (0, 0, 0xF, 0xB) => "SCR".to_string(),
+        (0, 0, 0xF, 0xC) => "SCL".to_string(),
+        (0, 0, 0xF, 0xE) => "LOW".to_string(),
+        (0, 0, 0xF, 0xF) => "HIGH".to_string(),
+        (0, _, _, _) => format!("SYS {nnn:#05X}"),
+        (1, _, _, _) => format!("JP {nnn:#05X}"),
+        (2, _, _, _) => format!("CALL {nnn:#05X}"),
+        (3, _, _, _) => format!("SE V{x:X}, {nn:#04X}"),
+        (4, _, _, _) => format!("SNE V{x:X}, {nn:#04X}"),
+        (5, _, _, 0) => format!("SE V{x:X}, V{y:X}"),
+        (6, _, _, _) => format!("LD V{x:X}, {nn:#04X}"),
+        (7, _, _, _) => format!("ADD V{x:X}, {nn:#04X}"),
+        (8, _, _, 0) => format!("LD V{x:X}, V{y:X}"),
+        (8, _, _, 1) => format!("OR V{x:X}, V{y:X}"),
+        (8, _, _, 2) => format!("AND V{x:X}, V{y:X}"),
+        (8, _, _, 3) => format!("XOR V{x:X}, V{y:X}"),
+        (8, _, _, 4) => format!("ADD V{x:X}, V{y:X}"),
+        (8, _, _, 5) => format!("SUB V{x:X}, V{y:X}"),
+        (8, _, _, 6) => format!("SHR V{x:X}, V{y:X}"),
+        (8, _, _, 7) => format!("SUBN V{x:X}, V{y:X}"),
+        (8, _, _, 0xE) => format!("SHL V{x:X}, V{y:X}"),
+        (9, _, _, 0) => format!("SNE V{x:X}, V{y:X}"),
+        (0xA, _, _, _) => format!("LD I, {nnn:#05X}"),
+        (0xB, _, _, _) => format!("JP V0, {nnn:#05X}"),
+        (0xC, _, _, _) => format!("RND V{x:X}, {nn:#04X}"),
+        (0xD, _, _, n) => format!("DRW V{x:X}, V{y:X}, {n:#03X}"),
+        (0xE, _, 0x9, 0xE) => format!("SKP V{x:X}"),
+        (0xE, _, 0xA, 0x1) => format!("SKNP V{x:X}"),
+        (0xF, _, 0x0, 0x7) => format!("LD V{x:X}, DT"),
+        (0xF, _, 0x0, 0xA) => format!("LD V{x:X}, K"),
+        (0xF, _, 0x1, 0x5) => format!("LD DT, V{x:X}"),
+        (0xF, _, 0x1, 0x8) => format!("LD ST, V{x:X}"),
+        (0xF, _, 0x1, 0xE) => format!("ADD I, V{x:X}"),
+        (0xF, _, 0x2, 0x9) => format!("LD F, V{x:X}"),
+        (0xF, _, 0x3, 0x3) => format!("LD B, V{x:X}"),
+        (0xF, _, 0x5, 0x5) => format!("LD [I], V{x:X}"),
+        (0xF, _, 0x6, 0x5) => format!("LD V{x:X}, [I]"),
+        _ => format!("DW {:#06X}", instruction.opcode())
+    }
+}
+
+/// Splits a line into its uppercased mnemonic and comma separated operands
+fn split_operands(line: &str) -> (String, Vec<String>) {
+    let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+    let operands = operands.split(',')
+        .map(|operand| operand.trim().to_uppercase())
+        .filter(|operand| !operand.is_empty())
+        .collect();
+
+    (mnemonic.to_uppercase(), operands)
+}
+
+/// An operand of an instruction after working out what kind it is
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(u16), // Vx
+    Value(u16), // A number or a label's address
+    I,
+    IndirectI, // [I]
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    Bcd
+}
+
+struct LineAssembler<'a> {
+    line: usize,
+    labels: &'a HashMap<String, u16>
+}
+
+impl LineAssembler<'_> {
+    fn error(&self, message: String) -> AsmError {
+        AsmError { line: self.line, message }
+    }
+
+    /// Parses a decimal, hex or label operand and checks it fits in `max`
+    fn number(&self, operand: &str, max: u16) -> Result<u16, AsmError> {
+        let value = if let Some(hex) = operand.strip_prefix("0X") {
+            u32::from_str_radix(hex, 16).ok()
+        } else if operand.starts_with(|c: char| c.is_ascii_digit()) {
+            operand.parse().ok()
+        } else {
+            self.labels.get(operand).map(|&address| address as u32)
+        };
+
+        match value {
+            Some(value) if value <= max as u32 => Ok(value as u16),
+            Some(_) => Err(self.error(format!("{operand} doesn't fit in {max:#X}"))),
+            None => Err(self.error(format!("unknown number or label \"{operand}\"")))
+        }
+    }
+
+    fn operand(&self, operand: &str) -> Result<Operand, AsmError> {
+        Ok(match operand {
+            "I" => Operand::I,
+            "[I]" => Operand::IndirectI,
+            "DT" => Operand::DelayTimer,
+            "ST" => Operand::SoundTimer,
+            "K" => Operand::Key,
+            "F" => Operand::Font,
+            "B" => Operand::Bcd,
+            _ => match operand.strip_prefix('V').map(|register| u16::from_str_radix(register, 16)) {
+                Some(Ok(register)) if register <= 0xF => Operand::Register(register),
+                _ => Operand::Value(self.number(operand, 0xFFFF)?)
+            }
+        })
+    }
+
+    /// Checks a value operand fits in `max` bits and returns it
+    fn value(&self, value: u16, max: u16) -> Result<u16, AsmError> {
+        if value > max {
+            return Err(self.error(format!("{value:#X} doesn't fit in {max:#X}")));
+        }
+
+        Ok(value)
+    }
+
+    fn instruction(&self, mnemonic: &str, operands: &[String]) -> Result<u16, AsmError> {
+        use Operand::*;
+
+        let operands = operands.iter()
+            .map(|operand| self.operand(operand))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(match (mnemonic, operands.as_slice()) {
+            ("CLS", []) => 0x00E0,
+            ("RET", []) => 0x00EE,
+            ("SCD", [Value(n)]) => 0x00C0 | self.value(*n, 0xF)?,
+            ("SCR", []) => 0x00FB,
+            ("SCL", []) => 0x00FC,
+            ("LOW", []) => 0x00FE,
+            ("HIGH", []) => 0x00FF,
+            ("SYS", [Value(nnn)]) => self.value(*nnn, 0xFFF)?,
+            ("JP", [Value(nnn)]) => 0x1000 | self.value(*nnn, 0xFFF)?,
+            ("JP", [Register(0), Value(nnn)]) => 0xB000 | self.value(*nnn, 0xFFF)?,
+            ("CALL", [Value(nnn)]) => 0x2000 | self.value(*nnn, 0xFFF)?,
+            ("SE", [Register(x), Value(nn)]) => 0x3000 | x << 8 | self.value(*nn, 0xFF)?,
+            ("SNE", [Register(x), Value(nn)]) => 0x4000 | x << 8 | self.value(*nn, 0xFF)?,
+            ("SE", [Register(x), Register(y)]) => 0x5000 | x << 8 | y << 4,
+            ("LD", [Register(x), Value(nn)]) => 0x6000 | x << 8 | self.value(*nn, 0xFF)?,
+            ("ADD", [Register(x), Value(nn)]) => 0x7000 | x << 8 | self.value(*nn, 0xFF)?,
+            ("LD", [Register(x), Register(y)]) => 0x8000 | x << 8 | y << 4,
+            ("OR", [Register(x), Register(y)]) => 0x8001 | x << 8 | y << 4,
+            ("AND", [Register(x), Register(y)]) => 0x8002 | x << 8 | y << 4,
+            ("XOR", [Register(x), Register(y)]) => 0x8003 | x << 8 | y << 4,
+            ("ADD", [Register(x), Register(y)]) => 0x8004 | x << 8 | y << 4,
+            ("SUB", [Register(x), Register(y)]) => 0x8005 | x << 8 | y << 4,
+            ("SHR", [Register(x)]) => 0x8006 | x << 8 | x << 4,
+            ("SHR", [Register(x), Register(y)]) => 0x8006 | x << 8 | y << 4,
+            ("SUBN", [Register(x), Register(y)]) => 0x8007 | x << 8 | y << 4,
+            ("SHL", [Register(x)]) => 0x800E | x << 8 | x << 4,
+            ("SHL", [Register(x), Register(y)]) => 0x800E | x << 8 | y << 4,
+            ("SNE", [Register(x), Register(y)]) => 0x9000 | x << 8 | y << 4,
+            ("LD", [I, Value(nnn)]) => 0xA000 | self.value(*nnn, 0xFFF)?,
+            ("RND", [Register(x), Value(nn)]) => 0xC000 | x << 8 | self.value(*nn, 0xFF)?,
+            ("DRW", [Register(x), Register(y), Value(n)]) => 0xD000 | x << 8 | y << 4 | self.value(*n, 0xF)?,
+            ("SKP", [Register(x)]) => 0xE09E | x << 8,
+            ("SKNP", [Register(x)]) => 0xE0A1 | x << 8,
+            ("LD", [Register(x), DelayTimer]) => 0xF007 | x << 8,
+            ("LD", [Register(x), Key]) => 0xF00A | x << 8,
+            ("LD", [DelayTimer, Register(x)]) => 0xF015 | x << 8,
+            ("LD", [SoundTimer, Register(x)]) => 0xF018 | x << 8,
+            ("ADD", [I, Register(x)]) => 0xF01E | x << 8,
+            ("LD", [Font, Register(x)]) => 0xF029 | x << 8,
+            ("LD", [Bcd, Register(x)]) => 0xF033 | x << 8,
+            ("LD", [IndirectI, Register(x)]) => 0xF055 | x << 8,
+            ("LD", [Register(x), IndirectI]) => 0xF065 | x << 8,
+            _ => return Err(self.error(format!("invalid instruction \"{mnemonic}\" with {} operand(s)", operands.len())))
+        })
+    }
+}
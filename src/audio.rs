@@ -0,0 +1,135 @@
+use rodio::{cpal::FromSample, source::SineWave, Sample, Source};
+use crate::error::Chip8Error;
+
+/// The audio output the emulator plays to, shared with the host so it can play its own sounds such as background music.
+/// Every sink plays at the same time, mixed together by rodio. The buzzer has a sink of its own so host sources never
+/// queue behind it or get paused with it, see `Chip8::audio_mixer`
+pub struct AudioMixer {
+    handle: rodio::OutputStreamHandle,
+    host: rodio::Sink, // Plays the sources appended by the host
+    #[allow(dead_code)] // Sinks stop playing once the output stream is dropped
+    stream: rodio::OutputStream
+}
+
+impl AudioMixer {
+    /// Opens the audio output with `open_output`, usually `rodio::OutputStream::try_default`. Fails on systems without
+    /// a device (CI, containers, some WSL setups)
+    fn open(open_output: impl FnOnce() -> Result<(rodio::OutputStream, rodio::OutputStreamHandle), rodio::StreamError>) -> Result<Self, Chip8Error> {
+        let (stream, handle) = open_output()
+            .map_err(|error| Chip8Error::AudioInit(format!("couldn't open the audio output device: {error}")))?;
+
+        let host = Self::sink_for(&handle)?;
+
+        Ok(Self { handle, host, stream })
+    }
+
+    fn sink_for(handle: &rodio::OutputStreamHandle) -> Result<rodio::Sink, Chip8Error> {
+        rodio::Sink::try_new(handle)
+            .map_err(|error| Chip8Error::AudioInit(format!("couldn't create the audio sink: {error}")))
+    }
+
+    /// Another sink playing alongside the others, for hosts that need more than one queue, e.g. music and sound effects
+    pub fn new_sink(&self) -> Result<rodio::Sink, Chip8Error> {
+        Self::sink_for(&self.handle)
+    }
+
+    /// Queues a source on the host's sink, it starts playing once everything appended before it finished
+    pub fn append<S>(&self, source: S) where S: Source + Send + 'static, f32: FromSample<S::Item>, S::Item: Sample + Send {
+        self.host.append(source);
+    }
+
+    pub fn play(&self) {
+        self.host.play();
+    }
+
+    pub fn pause(&self) {
+        self.host.pause();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.host.is_paused()
+    }
+
+    /// Drops every queued source
+    pub fn stop(&self) {
+        self.host.stop();
+    }
+
+    /// Whether every appended source finished playing
+    pub fn is_empty(&self) -> bool {
+        self.host.empty()
+    }
+
+    /// Sets the volume of the host's sources, 1.0 plays them unchanged. Independent of the buzzer's volume
+    pub fn set_volume(&self, volume: f32) {
+        self.host.set_volume(volume);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.host.volume()
+    }
+}
+
+/// The buzzer that sounds while the sound timer is non-zero
+pub struct Beeper {
+    sink: rodio::Sink,
+    volume: f32,
+    muted: bool,
+    mixer: AudioMixer // The output the beep plays to, on a sink of its own
+}
+
+impl Beeper {
+    /// Opens the default audio output device, see `AudioMixer`
+    pub fn try_new() -> Result<Self, Chip8Error> {
+        Self::open(rodio::OutputStream::try_default)
+    }
+
+    /// Plays to the output opened by `open_output` instead of the default device
+    pub fn open(open_output: impl FnOnce() -> Result<(rodio::OutputStream, rodio::OutputStreamHandle), rodio::StreamError>) -> Result<Self, Chip8Error> {
+        let mixer = AudioMixer::open(open_output)?;
+
+        // Add a dummy source of the sake of the example.
+        let source = SineWave::new(440.0).amplify(0.20).repeat_infinite();
+
+        let sink = mixer.new_sink()?;
+        sink.append(source);
+        sink.pause();
+
+        Ok(Self { sink, volume: 1.0, muted: false, mixer })
+    }
+
+    pub fn mixer(&self) -> &AudioMixer {
+        &self.mixer
+    }
+
+    pub fn play(&self) {
+        self.sink.play();
+    }
+
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+
+    /// Sets the volume between 0.0 (silent) and 1.0 (full). It's kept while muted and restored when unmuting
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.apply_volume();
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_volume();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    fn apply_volume(&self) {
+        self.sink.set_volume(if self.muted {0.0} else {self.volume});
+    }
+}
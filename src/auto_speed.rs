@@ -0,0 +1,124 @@
+//! The heuristic behind `Chip8::enable_auto_speed`. Most games wait on the delay timer to run at a steady pace, and
+//! every instruction they spend re-reading it in a loop is one the machine didn't need to run. Watching how much of
+//! each frame is spent that way says whether the speed can come down, while a game that never waits and rarely draws
+//! needs more. It only adjusts during the first few seconds after a ROM is loaded and then holds the speed steady
+
+/// How many frames are measured before adjusting the speed
+const WINDOW: u32 = 30;
+/// How many windows can adjust the speed before it's held steady, 5 seconds in total
+const MAX_WINDOWS: u32 = 10;
+/// How many windows in a row without a change settle the speed early
+const STEADY_WINDOWS: u32 = 2;
+/// The share of cycles spent waiting on the delay timer that's left over as headroom for busier scenes
+const TARGET_SPIN: f32 = 0.25;
+/// Below this share of waiting the game could use more speed, above `MAX_SPIN` it's running much faster than needed
+const MIN_SPIN: f32 = 0.05;
+const MAX_SPIN: f32 = 0.5;
+/// Games that never wait should draw in at least this share of frames to look smooth
+const MIN_DRAW_FRAMES: f32 = 0.5;
+
+/// The range the speed is kept within
+const MIN_CYCLES_PER_FRAME: u32 = 5;
+const MAX_CYCLES_PER_FRAME: u32 = 1000;
+
+#[derive(Default)]
+pub struct AutoSpeed {
+    windows: u32,
+    steady_windows: u32,
+    settled: bool,
+    frames: u32,
+    cycles: u32,
+    spins: u32, // Cycles spent re-reading the delay timer within a frame, where it can't have changed
+    draw_frames: u32,
+    drew_this_frame: bool,
+    last_timer_read: Option<(usize, u32)> // The address of the last FX07 this frame and the cycle it ran on
+}
+
+impl AutoSpeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Called after every instruction that ran, with the address it was fetched from
+    pub fn observe(&mut self, address: usize, opcode: u16) {
+        if self.settled {
+            return;
+        }
+
+        self.cycles += 1;
+
+        match opcode & 0xF0FF {
+            0xF007 => {
+                // Reading the timer again from the same place in the same frame is a loop waiting for it to change
+                if let Some((last_address, last_cycle)) = self.last_timer_read {
+                    if last_address == address {
+                        self.spins += self.cycles - last_cycle;
+                    }
+                }
+
+                self.last_timer_read = Some((address, self.cycles));
+            },
+            _ if opcode & 0xF000 == 0xD000 => self.drew_this_frame = true,
+            _ => {}
+        }
+    }
+
+    /// Called on every timer tick. Returns the new speed at the end of a window that changes it
+    pub fn end_frame(&mut self, cycles_per_frame: u32) -> Option<u32> {
+        if self.settled {
+            return None;
+        }
+
+        self.frames += 1;
+        self.draw_frames += self.drew_this_frame as u32;
+        self.drew_this_frame = false;
+        // The timer changes between frames, so the first read in the next one isn't waiting
+        self.last_timer_read = None;
+
+        if self.frames < WINDOW {
+            return None;
+        }
+
+        let new_speed = self.adjust(cycles_per_frame);
+        let measured = self.cycles > 0;
+        let (windows, steady_windows) = (self.windows, self.steady_windows);
+        *self = Self { windows, steady_windows, ..Self::default() };
+
+        // Paused, halted or waiting for a key the whole time, there was nothing to measure
+        if !measured {
+            return None;
+        }
+
+        self.windows += 1;
+        self.steady_windows = if new_speed.is_some() {0} else {self.steady_windows + 1};
+        self.settled = self.windows >= MAX_WINDOWS || self.steady_windows >= STEADY_WINDOWS;
+
+        new_speed
+    }
+
+    fn adjust(&self, cycles_per_frame: u32) -> Option<u32> {
+        if self.cycles == 0 {
+            return None;
+        }
+
+        let spin = self.spins as f32 / self.cycles as f32;
+        let draw_frames = self.draw_frames as f32 / self.frames as f32;
+
+        let new_speed = if spin > MAX_SPIN {
+            // Keeps TARGET_SPIN of the frame free, at most halving the speed at once to not undershoot
+            (cycles_per_frame as f32 * (1.0 - spin) / (1.0 - TARGET_SPIN)).max(cycles_per_frame as f32 / 2.0)
+        } else if spin < MIN_SPIN && self.draw_frames > 0 && (self.spins > 0 || draw_frames < MIN_DRAW_FRAMES) {
+            // Not when nothing was drawn at all, that is more likely a menu polling the keys than a game that is too slow
+            cycles_per_frame as f32 * 1.5
+        } else {
+            return None;
+        };
+
+        let new_speed = (new_speed.round() as u32).clamp(MIN_CYCLES_PER_FRAME, MAX_CYCLES_PER_FRAME);
+        (new_speed != cycles_per_frame).then_some(new_speed)
+    }
+}
@@ -1,66 +1,112 @@
-use crate::{display, keyboard};
-use rand;
+use crate::{asm, auto_speed::AutoSpeed, config::Chip8Config, display, error::Chip8Error, keyboard, profiles, quirks::{QuirkPreset, Quirks}, renderer, replay, instruction::Instruction};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::VecDeque, fmt, sync::Arc};
 use winit::event::VirtualKeyCode;
-use rodio::{source::SineWave, Source};
-
-#[derive(PartialEq, Eq, Debug)]
-struct Instruction {
-    digit1: u16,
-    digit2: u16,
-    digit3: u16,
-    digit4: u16
+#[cfg(feature = "audio")]
+use crate::audio;
+#[cfg(feature = "gif")]
+use crate::gif_recorder::GifRecorder;
+#[cfg(feature = "persist")]
+use crate::settings;
+
+/// How many bytes of memory the machine has by default, see `Chip8Config::ram_size`
+pub const RAM_SIZE: usize = 4096;
+
+/// Where programs are loaded and start executing. Everything below it was reserved for the original interpreter
+pub const PROGRAM_START: usize = 0x200;
+
+/// The address the hex digit sprites are loaded at by default. Most references and ROMs assume 0x050
+pub const FONT_BASE: usize = 0x050;
+
+/// The size of the font, 5 bytes for each of the 16 hex digits
+pub const FONT_LEN: usize = 80;
+
+/// How many times a second the timers tick, which is also how many frames run a second
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/// The largest program that fits in the default amount of memory after the interpreter's reserved bytes
+pub const MAX_PROGRAM_SIZE: usize = RAM_SIZE - PROGRAM_START;
+
+/// What a region of memory holds, see `Chip8::memory_map`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryRegionKind {
+    /// Belonged to the original interpreter, unused here
+    Reserved,
+    Font,
+    /// The loaded program and whatever memory it uses, up to the end of memory
+    Program
 }
 
-impl Instruction {
-    pub fn new(instruction: u16) -> Self {
-        Self {
-            digit1: (instruction & 0xF000) >> 12,
-            digit2: (instruction & 0x0F00) >> 8,
-            digit3: (instruction & 0x00F0) >> 4,
-            digit4: instruction & 0xF
-        }
-    }
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub kind: MemoryRegionKind,
+    pub range: std::ops::Range<usize>
+}
 
-    /// Returns the first byte of the instruction (ltr)
-    pub fn d1(&self) -> u16 {
-        self.digit1
-    }
+/// The machine's state right after an instruction executed, see `Chip8::run_trace`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The address the instruction was executed from
+    pub pc: u16,
+    pub opcode: u16,
+    pub registers: [u8; 0x10],
+    pub i_register: u16
+}
 
-    /// Returns the second byte of the instruction (ltr)
-    pub fn d2(&self) -> u16 {
-        self.digit2
-    }
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04X}: {:04X}", self.pc, self.opcode)?;
 
-    /// Returns the third byte of the instruction (ltr)
-    pub fn d3(&self) -> u16 {
-        self.digit3
-    }
+        for value in self.registers {
+            write!(f, " {value:02X}")?;
+        }
 
-    /// Returns the fourth byte of the instruction (ltr)
-    pub fn d4(&self) -> u16 {
-        self.digit4
-    }
-    
-    #[allow(dead_code)]
-    /// Returns the first and second bytes of the instruction (ltr)
-    pub fn xy(&self) -> u16 {
-        self.d1() << 4 | self.d2()
+        write!(f, " I:{:04X}", self.i_register)
     }
+}
 
-    /// Returns the third and fourth bytes of the instruction (ltr)
-    pub fn nn(&self) -> u16 {
-        self.digit3 << 4 | self.digit4
-    }
+/// Where the last `DXYN` drew from and to, see `Chip8::last_draw_info`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawInfo {
+    /// The address of the sprite's first byte, the I register when it was drawn
+    pub source_start: u16,
+    /// How many bytes of sprite were drawn, one per row
+    pub source_len: usize,
+    /// The sprite's top left corner on the screen. Whatever goes past the edges is wrapped or clipped depending on the quirks
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize
+}
 
-    /// Returns the second, third, and fourth byte of the instruction (ltr)
-    pub fn nnn(&self) -> u16 {
-        self.digit2 << 8 | self.digit3 << 4 | self.digit4
-    }
-    
+/// Where a debugging command that runs until something happens stopped, see `Chip8::run_until`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// Got where it was going
+    Reached,
+    /// Ran as many cycles as it was allowed to without getting there
+    CycleLimitReached,
+    /// The program jumped to itself
+    Halted,
+    /// The program is waiting for a key press
+    WaitingForKey
 }
 
+/// The parts of the machine a custom opcode handler can change, see `Chip8::set_custom_handler`
+pub struct Chip8Context<'a> {
+    pub registers: &'a mut [u8; 0x10],
+    pub i_register: &'a mut usize,
+    /// The address of the next instruction, already past the one being handled
+    pub pc: &'a mut usize,
+    pub delay_timer: &'a mut u8,
+    pub memory: &'a mut [u8]
+}
+
+/// Handles an opcode the interpreter doesn't know, returning whether it did
+type CustomHandler = Box<dyn FnMut(&mut Chip8Context, u16) -> bool>;
+
 pub struct Chip8 {
-    ram: [u8; 4096],
+    ram: Vec<u8>,
     registers: [u8; 0x10],
     i_register: usize,
     delay_timer: u8,
@@ -71,24 +117,189 @@ pub struct Chip8 {
     display: display::Display,
     keyboard: keyboard::Keyboard,
     pub paused: bool,
+    halted: bool,
+    detect_halt: bool,
+    halt_on_error: bool,
+    crash_screen: bool,
+    crash: Option<(Chip8Error, usize)>, // The error the crash screen is showing and the address of the failing instruction
+    start_paused: bool, // Whether `reset_and_reload` leaves the machine paused
+    waiting_for_vblank: bool, // Set by DXYN with the display_wait quirk or past the draw limit, execution stops until the next timer tick
+    max_draws_per_frame: Option<u32>,
+    draws_this_frame: u32,
+    draws: u64, // Every DXYN executed since the program was loaded, see `run_until_draw`
+    debug_overlay: bool,
+    #[cfg(feature = "log")]
+    trace_instructions: bool,
+    record_draws: bool,
+    last_draw: Option<DrawInfo>, // Only kept while recording draws
+    quirks: Quirks,
+    cycles_per_frame: u32,
+    ips: Option<u32>, // Paces `cycles_per_frame` every frame while set, see `set_ips`
+    ips_remainder: u32, // Instructions owed to the next frames, in 1/FRAMES_PER_SECOND of an instruction
+    auto_speed: Option<AutoSpeed>, // Tunes `cycles_per_frame` while enabled, see `enable_auto_speed`
+    foreground: [u8; 3],
+    background: [u8; 3],
+    rom_hash: u64,
+    rom_name: Option<String>, // Shown by hosts, see `set_rom_name`
+    program_len: usize, // How many bytes the loaded program takes after PROGRAM_START
+    known_profiles: Vec<profiles::Profile>, // Looked up by `apply_known_profile`
+    font_base: usize, // Where the hex digit sprites start in memory
+    rng: StdRng,
     current_instruction: Instruction, // Used to access the current instruction from any function in the cpu
-    beep: rodio::Sink,
-    #[allow(dead_code)] // rodio::Sink requires the output stream to live as long as it
-    stream: rodio::OutputStream
+    frame: u64, // Counts calls to `tick_timers`
+    recording: Option<(u64, Vec<replay::InputEvent>)>, // The frame the recording started on and the input since then
+    playback: Option<(u64, VecDeque<replay::InputEvent>)>, // The frame playback started on and the input left to replay
+    #[cfg(feature = "audio")]
+    beep: Option<audio::Beeper>, // None if no audio device could be opened, in which case the emulator runs silently
+    #[cfg(feature = "gif")]
+    gif_recording: Option<GifRecorder>,
+    on_sound_start: Option<Box<dyn FnMut()>>, // Replaces the built in beep when set
+    on_sound_stop: Option<Box<dyn FnMut()>>,
+    custom_handler: Option<CustomHandler> // Tried on unknown opcodes before failing
 }
 
 impl Chip8 {
-    pub fn new(window: &winit::window::Window) -> Self {
-        let ram = [0; 4096];
+    pub fn new(window: &winit::window::Window, gpu_config: &renderer::GpuConfig) -> Result<Self, Chip8Error> {
+        Self::from_config(Chip8Config { gpu: *gpu_config, ..Default::default() }, window)
+    }
+
+    /// Fails if no GPU can draw to the window, see `Chip8Error::GpuInit`, or the config is invalid like in
+    /// `headless_from_config`
+    pub fn from_config(config: Chip8Config, window: &winit::window::Window) -> Result<Self, Chip8Error> {
+        let display = display::Display::new(window, &config.gpu)?;
+
+        Self::with_output_display(config, display)
+    }
 
-        let (stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
+    /// Like `from_config` but renders with a GPU device shared with other machines, e.g. to run several side by side.
+    /// `config.gpu` is ignored since the context already picked the adapter
+    pub fn from_config_with_context(config: Chip8Config, context: Arc<renderer::GpuContext>, window: &winit::window::Window) -> Result<Self, Chip8Error> {
+        let display = display::Display::with_context(context, window)?;
 
-        // Add a dummy source of the sake of the example.
-        let source = SineWave::new(440.0).amplify(0.20).repeat_infinite();
+        Self::with_output_display(config, display)
+    }
+
+    /// Like `from_config` but shows the display with any backend instead of a window, e.g. a terminal frontend.
+    /// Sound plays as usual, use `headless_from_config` for a machine without any output. Fails like `headless_from_config`
+    pub fn from_config_with_backend(config: Chip8Config, backend: Box<dyn display::DisplayBackend>) -> Result<Self, Chip8Error> {
+        Self::with_output_display(config, display::Display::with_backend(backend))
+    }
+
+    /// Like `from_config_with_backend` but the beep plays to the output opened by `open_audio` instead of the default
+    /// device. If it fails the machine runs silently, like it does on systems without an audio device
+    #[cfg(feature = "audio")]
+    pub fn from_config_with_backend_and_audio(
+        config: Chip8Config,
+        backend: Box<dyn display::DisplayBackend>,
+        open_audio: impl FnOnce() -> Result<(rodio::OutputStream, rodio::OutputStreamHandle), rodio::StreamError>
+    ) -> Result<Self, Chip8Error> {
+        Self::with_output_display_and_beep(config, display::Display::with_backend(backend), audio::Beeper::open(open_audio))
+    }
+
+    fn with_output_display(config: Chip8Config, display: display::Display) -> Result<Self, Chip8Error> {
+        Self::with_output_display_and_beep(
+            config,
+            display,
+            #[cfg(feature = "audio")]
+            audio::Beeper::try_new()
+        )
+    }
+
+    fn with_output_display_and_beep(
+        config: Chip8Config,
+        mut display: display::Display,
+        #[cfg(feature = "audio")] beep: Result<audio::Beeper, Chip8Error>
+    ) -> Result<Self, Chip8Error> {
+        #[cfg(feature = "audio")]
+        let beep = beep
+            .map_err(|error| warn!("{error}, sound is disabled"))
+            .ok();
+
+        display.set_colors(config.foreground, config.background);
+
+        Self::with_display(
+            config,
+            display,
+            #[cfg(feature = "audio")]
+            beep
+        )
+    }
+
+    /// A machine without a window, GPU or sound. Everything but rendering and the beep is emulated as usual,
+    /// which makes it suitable for tests, benchmarks and tools
+    pub fn headless() -> Self {
+        Self::headless_from_config(Chip8Config::default()).expect("the default config is valid")
+    }
+
+    /// Like `headless` but configured, the colors, volume and GPU are ignored since nothing is rendered or played.
+    /// Fails if `config.ram_size` leaves no room for a program or the font doesn't fit where `config.font_base` puts it
+    pub fn headless_from_config(config: Chip8Config) -> Result<Self, Chip8Error> {
+        Self::with_display(
+            config,
+            display::Display::headless(),
+            #[cfg(feature = "audio")]
+            None
+        )
+    }
 
-        let beep = rodio::Sink::try_new(&stream_handle).unwrap();
-        beep.append(source);
-        beep.pause();
+    /// The seed `run_trace` uses for CXNN so traces are the same on every run
+    pub const TRACE_SEED: u64 = 0;
+
+    /// A ready to poke machine for tests and headless tools: headless, silent, with the default quirks and CXNN seeded
+    /// with `TRACE_SEED` so runs are reproducible. With the testing feature the registers, I and the pc can be set
+    /// directly instead of through instructions
+    pub fn test_instance() -> Self {
+        Self::headless_from_config(Chip8Config { rng_seed: Some(Self::TRACE_SEED), ..Chip8Config::default() })
+            .expect("the default config is valid")
+    }
+
+    /// Runs a program headless for up to `cycles` instructions and records the state after each one, to diff against
+    /// traces from other interpreters. It's deterministic: CXNN is seeded with `TRACE_SEED` and the timers tick every
+    /// `cycles_per_frame` instructions. The trace ends early if the program halts, waits for a key or an instruction
+    /// fails, the failing instruction isn't recorded. Fails only if the program can't be loaded
+    pub fn run_trace(program: Vec<u8>, cycles: u32) -> Result<Vec<TraceEntry>, Chip8Error> {
+        let mut chip8 = Self::headless_from_config(Chip8Config { rng_seed: Some(Self::TRACE_SEED), ..Default::default() })?;
+        chip8.load_program(program)?;
+
+        let mut trace = Vec::new();
+
+        for cycle in 0..cycles {
+            if cycle > 0 && cycle % chip8.cycles_per_frame == 0 {
+                chip8.tick_timers();
+            }
+
+            if chip8.halted || chip8.keyboard.awaiting_key_press {
+                break;
+            }
+
+            let pc = chip8.pc as u16;
+
+            if chip8.execute_cycle().is_err() {
+                break;
+            }
+
+            trace.push(TraceEntry {
+                pc,
+                opcode: chip8.current_instruction.opcode(),
+                registers: chip8.registers,
+                i_register: chip8.i_register as u16
+            });
+        }
+
+        Ok(trace)
+    }
+
+    fn with_display(config: Chip8Config, display: display::Display, #[cfg(feature = "audio")] beep: Option<audio::Beeper>) -> Result<Self, Chip8Error> {
+        if config.ram_size <= PROGRAM_START {
+            return Err(Chip8Error::RamTooSmall(config.ram_size));
+        }
+
+        let ram = vec![0; config.ram_size];
+
+        let rng = match config.rng_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
 
         let mut chip8 = Chip8 {
             ram, 
@@ -96,30 +307,162 @@ impl Chip8 {
             i_register: 0,
             delay_timer: 0,
             sound_timer: 0,
-            pc: 0x200, 
+            pc: PROGRAM_START,
             stack_ptr: 0, 
             stack: [0; 16], 
-            display: display::Display::new(window),
-            keyboard: keyboard::Keyboard::new(),
-            paused: false,
+            display,
+            keyboard: keyboard::Keyboard::with_key_map(config.key_map),
+            paused: config.start_paused,
+            halted: false,
+            waiting_for_vblank: false,
+            max_draws_per_frame: None,
+            draws_this_frame: 0,
+            draws: 0,
+            detect_halt: config.detect_halt,
+            halt_on_error: config.halt_on_error,
+            crash_screen: config.crash_screen,
+            crash: None,
+            start_paused: config.start_paused,
+            debug_overlay: false,
+            #[cfg(feature = "log")]
+            trace_instructions: false,
+            record_draws: false,
+            last_draw: None,
+            quirks: config.quirks,
+            cycles_per_frame: config.cycles_per_frame,
+            ips: None,
+            ips_remainder: 0,
+            auto_speed: None,
+            foreground: config.foreground,
+            background: config.background,
+            rom_hash: Self::hash_rom(&[]),
+            rom_name: None,
+            program_len: 0,
+            known_profiles: profiles::built_in(),
+            font_base: config.font_base,
+            rng,
             current_instruction: Instruction::new(0x0),
+            frame: 0,
+            recording: None,
+            playback: None,
+            #[cfg(feature = "audio")]
             beep,
-            stream
+            #[cfg(feature = "gif")]
+            gif_recording: None,
+            on_sound_start: None,
+            on_sound_stop: None,
+            custom_handler: None
         };
 
-        chip8.load_sprites_into_memory();
+        chip8.load_sprites_into_memory()?;
+        chip8.set_quirks(config.quirks);
+        chip8.set_latch_keys(config.latch_keys);
+        #[cfg(feature = "audio")]
+        chip8.set_volume(config.volume);
+        // Dims the screen when starting paused
+        chip8.refresh_overlays();
 
-        chip8
+        Ok(chip8)
     }
 
-    pub fn load_program(&mut self, program: Vec<u8>) {
+    /// Copies the program into memory at `PROGRAM_START`, failing if it's larger than `max_program_size`
+    pub fn load_program(&mut self, program: Vec<u8>) -> Result<(), Chip8Error> {
+        if program.len() > self.max_program_size() {
+            return Err(Chip8Error::RomTooLarge);
+        }
+
+        self.rom_hash = Self::hash_rom(&program);
+        self.program_len = program.len();
+        debug!("Loaded a {} byte ROM with hash {:016x}", program.len(), self.rom_hash);
+
         for (i, byte) in program.into_iter().enumerate() {
-            self.ram[0x200+i] = byte;
+            self.ram[PROGRAM_START + i] = byte;
         }
+
+        Ok(())
+    }
+
+    /// 64 bit FNV-1a, simple and good enough to tell ROMs apart
+    fn hash_rom(program: &[u8]) -> u64 {
+        program.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
     }
 
-    fn load_sprites_into_memory(&mut self) {
-        let sprites: [u8; 80] = [
+    /// Identifies the loaded program, used as the key for per game settings
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// How many bytes the loaded program takes, 0 before one is loaded
+    pub fn program_len(&self) -> usize {
+        self.program_len
+    }
+
+    /// The addresses the loaded program was copied to. Anything after it is padding or memory the program uses,
+    /// so tools reading instructions out of memory can stop at its end
+    pub fn program_range(&self) -> std::ops::Range<usize> {
+        PROGRAM_START..PROGRAM_START + self.program_len
+    }
+
+    /// Names the loaded program for hosts to show, e.g. its file name. Cleared by `reset_and_reload`
+    pub fn set_rom_name(&mut self, name: impl Into<String>) {
+        self.rom_name = Some(name.into());
+    }
+
+    pub fn rom_name(&self) -> Option<&str> {
+        self.rom_name.as_deref()
+    }
+
+    /// Puts the machine back in the state it was created in and loads a new program. It starts running right away unless
+    /// the machine was configured with `start_paused`, in which case it's paused until `paused` is cleared.
+    /// The configuration (quirks, key map, colors, ...) and sound callbacks are kept
+    pub fn reset_and_reload(&mut self, program: Vec<u8>) -> Result<(), Chip8Error> {
+        // Checked up front so a ROM that doesn't fit leaves the running one untouched
+        if program.len() > self.max_program_size() {
+            return Err(Chip8Error::RomTooLarge);
+        }
+
+        self.stop_sound();
+
+        self.ram.fill(0);
+        self.registers = [0; 0x10];
+        self.i_register = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.pc = PROGRAM_START;
+        self.stack_ptr = 0;
+        self.stack = [0; 16];
+        self.last_draw = None;
+        // Also clears the screen
+        self.display.set_resolution(display::Display::WIDTH, display::Display::HEIGHT);
+        self.keyboard.reset();
+        self.paused = self.start_paused;
+        self.crash = None;
+        self.halted = false;
+        self.waiting_for_vblank = false;
+        self.draws_this_frame = 0;
+        self.draws = 0;
+        self.current_instruction = Instruction::new(0x0);
+        self.frame = 0;
+        self.rom_name = None;
+        self.recording = None;
+        self.playback = None;
+
+        // The new ROM may want a different speed
+        if self.auto_speed.is_some() {
+            self.auto_speed = Some(AutoSpeed::new());
+        }
+
+        self.load_sprites_into_memory()?;
+        self.load_program(program)
+    }
+
+    /// Fails if the font would overlap the program or run past the end of memory
+    fn load_sprites_into_memory(&mut self) -> Result<(), Chip8Error> {
+        if self.font_base + FONT_LEN > PROGRAM_START.min(self.ram.len()) {
+            return Err(Chip8Error::FontOutOfRange(self.font_base));
+        }
+
+        let sprites: [u8; FONT_LEN] = [
             0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
             0x20, 0x60, 0x20, 0x20, 0x70, // 1
             0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
@@ -139,26 +482,60 @@ impl Chip8 {
         ];
 
         for (i, byte) in sprites.into_iter().enumerate() {
-            self.ram[i] = byte;
+            self.ram[self.font_base + i] = byte;
         }
+
+        Ok(())
     }
 
-    fn fetch_instruction(&mut self) {
+    /// Fails if the pc is on the last byte of memory or past it, since the instruction would be cut off
+    fn fetch_instruction(&mut self) -> Result<(), Chip8Error> {
+        if self.quirks.pc_wrap {
+            self.wrap_pc();
+        } else {
+            self.memory_range(self.pc, 2)?;
+        }
+
+        // With the pc_wrap quirk an instruction on the last byte continues at the start of memory
         let first_byte: u16 = self.ram[self.pc] as u16;
-        let second_byte: u16 = self.ram[self.pc + 1] as u16;
+        let second_byte: u16 = self.ram[(self.pc + 1) % self.ram.len()] as u16;
         let instruction: u16 = first_byte << 8 | second_byte;
 
         self.next_instruction();
     
         self.current_instruction = Instruction::new(instruction);
+
+        Ok(())
     }
 
     /// Goes to the next instruction (also used to skip over instuctions)
     fn next_instruction(&mut self) {
         self.pc += 2;
+        self.wrap_pc();
+    }
+
+    /// The address the current instruction was fetched from. Only valid until the instruction changes the pc
+    fn instruction_address(&self) -> usize {
+        // Masked since the pc may have wrapped around to 0x000 with the pc_wrap quirk
+        (self.pc + self.ram.len() - 2) % self.ram.len()
+    }
+
+    /// Keeps the pc within memory with the pc_wrap quirk, see `Quirks::pc_wrap`
+    fn wrap_pc(&mut self) {
+        if self.quirks.pc_wrap {
+            self.pc %= self.ram.len();
+        }
+    }
+
+    /// Skips over the next instruction when the condition holds
+    fn skip_instruction_if(&mut self, condition: bool) {
+        if condition {
+            self.next_instruction();
+        }
     }
 
-    fn excecute_instruction(&mut self) {
+    /// New arms should also be described in `instruction::supported_opcodes`
+    fn excecute_instruction(&mut self) -> Result<(), Chip8Error> {
         let instruction = &self.current_instruction;
     
         match (instruction.d1(), instruction.d2(), instruction.d3(), instruction.d4()) {
@@ -166,37 +543,61 @@ impl Chip8 {
                 self.display.clear_screen();
             },
             (0, 0, 0xE, 0xE) => { // Return excecution to stored address
-                // TODO: Maybe crash the program if try to access when stack_ptr is already 0 and causes a stack overflow
+                if self.stack_ptr == 0 {
+                    return Err(Chip8Error::StackUnderflow);
+                }
+
                 self.stack_ptr -= 1;
                 self.pc = self.stack[self.stack_ptr] as usize;
             }
+            (0, 0, 0xC, n) => { // Scroll down n rows (SCHIP)
+                let rows = if self.scrolls_halved() {(n as usize).div_ceil(2)} else {n as usize};
+                self.display.scroll_down(rows);
+            },
+            (0, 0, 0xF, 0xB) => { // Scroll right 4 columns (SCHIP)
+                let columns = if self.scrolls_halved() {2} else {4};
+                self.display.scroll_right(columns);
+            },
+            (0, 0, 0xF, 0xC) => { // Scroll left 4 columns (SCHIP)
+                let columns = if self.scrolls_halved() {2} else {4};
+                self.display.scroll_left(columns);
+            },
+            (0, 0, 0xF, 0xE) => { // Switch to lowres (SCHIP)
+                self.display.set_resolution(display::Display::WIDTH, display::Display::HEIGHT);
+            },
+            (0, 0, 0xF, 0xF) => { // Switch to hires (SCHIP)
+                self.display.set_resolution(display::Display::MAX_WIDTH, display::Display::MAX_HEIGHT);
+            },
             (1, _, _, _) => { // Jump program counter to nnn
-                self.pc = (instruction.nnn()) as usize;
+                let target = instruction.nnn() as usize;
+
+                // A jump to itself is how most ROMs halt, so there's no point in executing it until something changes
+                if self.detect_halt && target == self.instruction_address() {
+                    self.halted = true;
+                }
+
+                self.pc = target;
             },
             (2, _, _, _) => { // Same as above but store the current excecuting instruction to later return
+                if self.stack_ptr == self.stack.len() {
+                    return Err(Chip8Error::StackOverflow);
+                }
+
                 self.stack[self.stack_ptr] = self.pc as u16;
                 self.pc = instruction.nnn() as usize;
                 self.stack_ptr += 1;
             },
             (3, x, _, _) => { // Skip instruction if vx == nn
-                if self.registers[x as usize] == instruction.nn() as u8 {
-                    self.next_instruction()
-                }
+                self.skip_instruction_if(self.registers[x as usize] == instruction.nn() as u8);
             },
             (4, x, _, _) => { // Skip instruction if vx != nn
-                if self.registers[x as usize] != instruction.nn() as u8 {
-                    self.next_instruction()
-                }
+                self.skip_instruction_if(self.registers[x as usize] != instruction.nn() as u8);
             },
             (5, x, y, 0) => { // Skip instruction if vx == vy
-                if self.registers[x as usize] == self.registers[y as usize] as u8 {
-                    self.next_instruction()
-                }
+                self.skip_instruction_if(self.registers[x as usize] == self.registers[y as usize]);
             },
             (9, x, y, 0) => { // Skip instruction if vx != vy
-                if self.registers[x as usize] != self.registers[y as usize] as u8 {
-                    self.next_instruction()
-                }
+                self.skip_instruction_if(self.registers[x as usize] != self.registers[y as usize]);
             },
             (6, x, _, _) => { // Set vx to nn
                 self.registers[x as usize] = instruction.nn() as u8;
@@ -234,8 +635,10 @@ impl Chip8 {
                 self.registers[x as usize] = new_vx;
                 self.registers[0xF] = if underflow {0} else {1};
             },
-            (8,x, y, 6) => { // Set vx to vy (optional depending on interpretation), vx >>= 1, vf = shifted out bit
-                self.registers[x as usize] = self.registers[y as usize];
+            (8,x, y, 6) => { // Set vx to vy with the shift_uses_vy quirk, vx >>= 1, vf = shifted out bit (set last so it wins over vx = vf)
+                if self.quirks.shift_uses_vy {
+                    self.registers[x as usize] = self.registers[y as usize];
+                }
 
                 let og_vx = self.registers[x as usize];
 
@@ -245,8 +648,10 @@ impl Chip8 {
 
                 self.registers[0xF] = shifted_bit;
             },
-            (8,x, y, 0xE) => { // Set vx to vy (optional depending on interpretation), vx <<= 1, vf = shifted out bit
-                self.registers[x as usize] = self.registers[y as usize];
+            (8,x, y, 0xE) => { // Set vx to vy with the shift_uses_vy quirk, vx <<= 1, vf = shifted out bit (set last so it wins over vx = vf)
+                if self.quirks.shift_uses_vy {
+                    self.registers[x as usize] = self.registers[y as usize];
+                }
 
                 let og_vx = self.registers[x as usize];
 
@@ -259,29 +664,45 @@ impl Chip8 {
             (0xA, _, _, _) => { // i register = nnn
                 self.i_register = instruction.nnn() as usize;
             },
-            (0xB, _, _, _) => { // Set pc = nnn + v0 (could also be interpreted as 0xBxnn where it would set pc = nnn + vx)
-                self.pc = (instruction.nnn() + self.registers[0] as u16) as usize;
+            (0xB, x, _, _) => { // Set pc = nnn + v0 (could also be interpreted as 0xBxnn where it would set pc = nnn + vx)
+                let offset = if self.quirks.jump_uses_vx {self.registers[x as usize]} else {self.registers[0]};
+
+                self.pc = (instruction.nnn() + offset as u16) as usize;
+                self.wrap_pc();
             },
             (0xC, x, _, _) => { // Set vx = random() & nn
-                self.registers[x as usize] = rand::random::<u8>() & instruction.nn() as u8;
+                self.registers[x as usize] = self.rng.gen::<u8>() & instruction.nn() as u8;
             },
             (0xD, x, y, n) => {
                 let x = self.registers[x as usize];
                 let y = self.registers[y as usize];
-                let from = self.i_register;
-                let to = from + (n as usize);
+                let sprite = self.memory_range(self.i_register, n as usize)?;
 
-                self.registers[0xF] = self.display.draw(x, y, &self.ram[from..to]) as u8;
+                if self.record_draws {
+                    self.last_draw = Some(DrawInfo {
+                        source_start: sprite.start as u16,
+                        source_len: sprite.len(),
+                        x: x as usize % self.display.width(),
+                        y: y as usize % self.display.height(),
+                        width: 8,
+                        height: n as usize
+                    });
+                }
+
+                self.registers[0xF] = self.display.draw(x, y, &self.ram[sprite]) as u8;
+                self.draws_this_frame += 1;
+                self.draws += 1;
+
+                // display_wait is the COSMAC VIP's display interrupt, which only let a sprite be drawn once per frame
+                if self.quirks.display_wait || self.max_draws_per_frame.is_some_and(|max| self.draws_this_frame >= max) {
+                    self.waiting_for_vblank = true;
+                }
             },
             (0xE, x, _0x9, 0xE) => {
-                if self.keyboard.is_pressed(self.registers[x as usize]) {
-                    self.next_instruction();
-                }
+                self.skip_instruction_if(self.keyboard.is_pressed_this_frame(self.registers[x as usize]));
             },
             (0xE, x, 0xA, 0x1) => {
-                if !self.keyboard.is_pressed(self.registers[x as usize]) {
-                    self.next_instruction();
-                }
+                self.skip_instruction_if(!self.keyboard.is_pressed_this_frame(self.registers[x as usize]));
             },
             (0xF, x, 0x0, 0x7) => {
                 self.registers[x as usize] = self.delay_timer;
@@ -290,15 +711,13 @@ impl Chip8 {
                 self.delay_timer = self.registers[x as usize];
             },
             (0xF, x, 0x1, 0x8) => {
-                self.sound_timer = self.registers[x as usize];
-                if self.sound_timer > 0 {
-                    self.beep.play();
-                }
+                self.set_sound_timer(self.registers[x as usize]);
             },
             (0xF, x, 0x1, 0xE) => {
                 self.i_register += self.registers[x as usize] as usize;
-                if self.i_register > 0x0FFF {
-                    self.registers[0xF] = 1;
+
+                if self.quirks.fx1e_sets_vf {
+                    self.registers[0xF] = (self.i_register >= self.ram.len()) as u8;
                 }
             },
             (0xF, _, 0, 0xA) => {
@@ -306,9 +725,14 @@ impl Chip8 {
             },
             (0xF, x, 0x2, 0x9) => 
             {
-                self.i_register = self.registers[x as usize] as usize * 5;
+                // Only the low nibble picks the digit, so a value above 0xF can't point I past the font into the program
+                let digit = self.registers[x as usize] & 0x0F;
+
+                self.i_register = self.font_base + digit as usize * 5;
             },
             (0xF, x, 0x3, 0x3) => {
+                self.memory_range(self.i_register, 3)?;
+
                 let num = self.registers[x as usize];
 
                 // Since its integer division the decimal places are ignored, effectively removing them
@@ -317,37 +741,725 @@ impl Chip8 {
                 self.ram[self.i_register + 2] = num % 10; // The tens digit
             },
             (0xF, x, 0x5, 0x5) => {
+                self.memory_range(self.i_register, x as usize + 1)?;
+
                 for i in 0..=x as usize {
                     self.ram[self.i_register + i] = self.registers[i];
                 }
+
+                if self.quirks.load_store_increments_i {
+                    self.i_register += x as usize + 1;
+                }
             },
             (0xF, x, 0x6, 0x5) => {
+                self.memory_range(self.i_register, x as usize + 1)?;
+
                 for i in 0..=x as usize {
                     self.registers[i] = self.ram[self.i_register + i];
                 }
+
+                if self.quirks.load_store_increments_i {
+                    self.i_register += x as usize + 1;
+                }
+            }
+            _ => {
+                let opcode = instruction.opcode();
+
+                if !self.run_custom_handler(opcode) {
+                    return Err(Chip8Error::UnknownOpcode(opcode));
+                }
             }
-            _ => {}
         }
+
+        Ok(())
     }
 
-    pub fn cycle(&mut self) {
+    /// Whether the SCHIP scrolls move half as far, which is only the case in lowres with the `lowres_scroll_halved` quirk
+    fn scrolls_halved(&self) -> bool {
+        self.quirks.lowres_scroll_halved && self.display.width() == display::Display::WIDTH
+    }
+
+    /// Whether the custom handler handled the opcode, false if there's none
+    fn run_custom_handler(&mut self, opcode: u16) -> bool {
+        let Some(handler) = &mut self.custom_handler else {
+            return false;
+        };
+
+        let mut context = Chip8Context {
+            registers: &mut self.registers,
+            i_register: &mut self.i_register,
+            pc: &mut self.pc,
+            delay_timer: &mut self.delay_timer,
+            memory: &mut self.ram
+        };
+
+        handler(&mut context, opcode)
+    }
+
+    /// The addresses of `length` bytes of memory starting at `start`, failing if any of them is past the end of memory
+    fn memory_range(&self, start: usize, length: usize) -> Result<std::ops::Range<usize>, Chip8Error> {
+        if start + length > self.ram.len() {
+            return Err(Chip8Error::OutOfBoundsMemory(start.max(self.ram.len())));
+        }
+
+        Ok(start..start + length)
+    }
+
+    pub fn cycle(&mut self) -> Result<(), Chip8Error> {
         if !self.paused {
-            if self.keyboard.recieved_key_press {
-                self.handle_await_keypress();
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs up to `cycles` cycles, stopping early if the machine gets paused, halts or waits for the next frame. Returns how many cycles actually ran.
+    /// An instruction failing stops the batch, the pc is left after the failing instruction
+    pub fn run_cycles(&mut self, cycles: u32) -> Result<u32, Chip8Error> {
+        let mut executed = 0;
+        let mut result = Ok(());
+
+        while executed < cycles && !self.paused && !self.halted && !self.waiting_for_vblank && result.is_ok() {
+            result = self.execute_cycle();
+            executed += 1;
+        }
+
+        self.refresh_overlays();
+
+        result.map(|_| executed)
+    }
+
+    /// Whether the program jumped to itself and is waiting for input. Timers keep ticking and the display keeps
+    /// rendering while halted, only instructions stop executing
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Whether execution stopped until the next timer tick after a draw, see `Quirks::display_wait` and
+    /// `set_max_draws_per_frame`
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.waiting_for_vblank
+    }
+
+    /// Whether FX0A is waiting for a key press, no instructions execute until one arrives
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.keyboard.awaiting_key_press
+    }
+
+    /// How many instructions run every 60Hz frame
+    pub fn cycles_per_frame(&self) -> u32 {
+        self.cycles_per_frame
+    }
+
+    /// Also stops pacing by `set_ips`. While auto speed is enabled it goes on tuning from this speed, see
+    /// `enable_auto_speed`
+    pub fn set_cycles_per_frame(&mut self, cycles_per_frame: u32) {
+        self.cycles_per_frame = cycles_per_frame;
+        self.ips = None;
+    }
+
+    /// Runs `ips` instructions a second, for speeds that aren't a multiple of `FRAMES_PER_SECOND`. Every timer tick
+    /// sets `cycles_per_frame` to the whole instructions owed so far and carries the fraction over to the next frame,
+    /// so 700 IPS runs frames of 11 and 12 instructions that average out to exactly 700 every second. Lasts until
+    /// `set_cycles_per_frame` picks a fixed speed again or auto speed is enabled. Disables auto speed since both would
+    /// be picking `cycles_per_frame`
+    pub fn set_ips(&mut self, ips: u32) {
+        self.auto_speed = None;
+        self.ips = Some(ips);
+        self.ips_remainder = 0;
+        self.pace_frame();
+    }
+
+    /// The instructions per second set by `set_ips`, None while running a fixed `cycles_per_frame`
+    pub fn ips(&self) -> Option<u32> {
+        self.ips
+    }
+
+    /// Picks how many instructions the next frame runs while pacing by IPS
+    fn pace_frame(&mut self) {
+        if let Some(ips) = self.ips {
+            // Counted in fractions of an instruction so the remainder carries over exactly
+            let owed = self.ips_remainder + ips;
+            self.cycles_per_frame = owed / FRAMES_PER_SECOND;
+            self.ips_remainder = owed % FRAMES_PER_SECOND;
+        }
+    }
+
+    /// Experimental: over the first few seconds after a ROM is loaded, watches how often the game draws and how much
+    /// time it spends waiting on the delay timer, and moves `cycles_per_frame` toward a speed that draws smoothly
+    /// without spinning needlessly. The speed is then held steady until the next ROM. Off by default, disabling it
+    /// keeps whatever speed it reached. Enabling it stops pacing by `set_ips` since both would be picking
+    /// `cycles_per_frame`, it starts tuning from the speed the pacing last picked
+    pub fn enable_auto_speed(&mut self, enabled: bool) {
+        if enabled {
+            self.ips = None;
+        }
+
+        self.auto_speed = enabled.then(AutoSpeed::new);
+    }
+
+    /// Whether auto speed is tuning `cycles_per_frame`, see `enable_auto_speed`
+    pub fn is_auto_speed_enabled(&self) -> bool {
+        self.auto_speed.is_some()
+    }
+
+    /// Whether auto speed is done adjusting `cycles_per_frame`, false when it's disabled
+    pub fn is_auto_speed_settled(&self) -> bool {
+        self.auto_speed.as_ref().is_some_and(AutoSpeed::is_settled)
+    }
+
+    /// Stops executing for the rest of the frame after `max` sprites were drawn, so a game drawing more than that per
+    /// frame is slowed down to `max` and flickers less, without locking every draw to the frame like the display_wait
+    /// quirk, which acts like a limit of 1. None removes the limit.
+    ///
+    /// Whatever is left of `cycles_per_frame` when the limit is reached isn't run, so a low limit makes the game run
+    /// fewer instructions than configured. The other way around, the limit only matters if `cycles_per_frame` is high
+    /// enough for that many draws to happen in one frame
+    pub fn set_max_draws_per_frame(&mut self, max: Option<u32>) {
+        self.max_draws_per_frame = max;
+    }
+
+    pub fn max_draws_per_frame(&self) -> Option<u32> {
+        self.max_draws_per_frame
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.display.set_wrapping(quirks.wrap_x, quirks.wrap_y);
+    }
+
+    /// Whether `EX9E`/`EXA1` see the keys down at the last timer tick, see `Chip8Config::latch_keys`
+    pub fn set_latch_keys(&mut self, enabled: bool) {
+        self.keyboard.set_latching(enabled);
+    }
+
+    /// Switches to the quirks of a whole platform at once, see `Quirks::preset`
+    pub fn apply_preset(&mut self, preset: QuirkPreset) {
+        self.set_quirks(Quirks::preset(preset));
+    }
+
+    /// Adds profiles to look through in `apply_known_profile`. They take priority over the ones already known,
+    /// so they can correct the built in ones
+    pub fn add_known_profiles(&mut self, profiles: Vec<profiles::Profile>) {
+        self.known_profiles.splice(0..0, profiles);
+    }
+
+    /// Applies the quirks and speed the loaded ROM is known to need, looked up by its hash and then by the name given
+    /// to `set_rom_name`. Returns the name of the profile that was applied, None if the ROM isn't known
+    pub fn apply_known_profile(&mut self) -> Option<String> {
+        let profile = self.known_profiles.iter()
+            .find(|profile| profile.matches_hash(self.rom_hash))
+            .or_else(|| {
+                let rom_name = self.rom_name.as_deref()?;
+                self.known_profiles.iter().find(|profile| profile.matches_name(rom_name))
+            })?
+            .clone();
+
+        self.set_quirks(profile.quirks);
+        self.set_cycles_per_frame(profile.cycles_per_frame);
+
+        Some(profile.name)
+    }
+
+    /// Whether the display changed since it was last rendered. Hosts can use it to skip presenting idle frames,
+    /// see `render`
+    pub fn needs_redraw(&self) -> bool {
+        self.display.is_dirty()
+    }
+
+    /// The screen's current pixels
+    pub fn display(&self) -> &display::Display {
+        &self.display
+    }
+
+    /// Shows a screen drawn by the host, see `Display::set_framebuffer`. The program can draw over it as usual
+    pub fn set_display(&mut self, framebuffer: &[[bool; display::Display::WIDTH]; display::Display::HEIGHT]) {
+        self.display.set_framebuffer(framebuffer);
+    }
+
+    /// The screen packed a bit per pixel, see `Display::to_packed_bits`
+    pub fn packed_display(&self) -> Vec<u8> {
+        self.display.to_packed_bits()
+    }
+
+    /// Replaces the screen with pixels from `packed_display`, see `Display::set_packed_bits`
+    pub fn set_packed_display(&mut self, bits: &[u8]) -> Result<(), Chip8Error> {
+        self.display.set_packed_bits(bits)
+    }
+
+    /// Counts the pixels that differ from the given screen, see `Display::diff`
+    pub fn display_diff(&self, other: &[[bool; display::Display::WIDTH]; display::Display::HEIGHT]) -> usize {
+        self.display.diff(other)
+    }
+
+    /// The colors of pixels that are on and off as sRGB
+    pub fn colors(&self) -> ([u8; 3], [u8; 3]) {
+        (self.foreground, self.background)
+    }
+
+    pub fn set_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) {
+        self.foreground = foreground;
+        self.background = background;
+        self.display.set_colors(foreground, background);
+    }
+
+    /// See `Display::set_ghost_pixels`
+    #[cfg(feature = "debug")]
+    pub fn set_ghost_pixels(&mut self, enabled: bool) {
+        self.display.set_ghost_pixels(enabled);
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn is_ghost_pixels(&self) -> bool {
+        self.display.is_ghost_pixels()
+    }
+
+    /// See `Display::set_color_cycle`
+    pub fn set_color_cycle(&mut self, enabled: bool, speed: f32) {
+        self.display.set_color_cycle(enabled, speed);
+    }
+
+    /// Applies the settings saved for the loaded ROM. Returns whether any were found
+    #[cfg(feature = "persist")]
+    pub fn load_settings(&mut self) -> bool {
+        let Some(settings) = settings::load(self.rom_hash) else {
+            return false;
+        };
+
+        self.set_quirks(settings.quirks);
+        self.set_cycles_per_frame(settings.cycles_per_frame);
+        self.set_colors(settings.foreground, settings.background);
+
+        true
+    }
+
+    /// Saves the current quirks, speed and colors for the loaded ROM so `load_settings` can restore them
+    #[cfg(feature = "persist")]
+    pub fn save_settings(&self) -> std::io::Result<()> {
+        settings::save(self.rom_hash, settings::GameSettings {
+            quirks: self.quirks,
+            cycles_per_frame: self.cycles_per_frame,
+            foreground: self.foreground,
+            background: self.background
+        })
+    }
+
+    /// Executes a single instruction even while paused
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        let result = self.execute_cycle();
+        self.refresh_overlays();
+
+        result
+    }
+
+    fn execute_cycle(&mut self) -> Result<(), Chip8Error> {
+        self.fetch_and_execute().map_err(|(error, address)| {
+            // The failing instruction's effects are kept so they can be inspected
+            if self.halt_on_error {
+                self.paused = true;
+
+                if self.crash_screen {
+                    self.crash = Some((error.clone(), address));
+                }
             }
 
-            if !self.keyboard.awaiting_key_press {
-                self.fetch_instruction();
-                self.excecute_instruction();
+            error
+        })
+    }
+
+    /// Fails with the error and the address of the instruction that raised it
+    fn fetch_and_execute(&mut self) -> Result<(), (Chip8Error, usize)> {
+        if self.keyboard.recieved_key_press {
+            self.handle_await_keypress();
+        }
+
+        if !self.keyboard.awaiting_key_press && !self.halted && !self.waiting_for_vblank {
+            // The pc only moves once the instruction was fetched
+            self.fetch_instruction().map_err(|error| {
+                error!("{error} while fetching the instruction at {:04X}", self.pc);
+                (error, self.pc)
+            })?;
+
+            // Taken before executing since jumps move the pc
+            let address = self.instruction_address();
+
+            #[cfg(feature = "log")]
+            if self.trace_instructions {
+                trace!("{address:04X}: {:04X}", self.current_instruction.opcode());
+            }
+
+            // The address taken before executing, the failing instruction may have moved the pc already
+            self.excecute_instruction().map_err(|error| {
+                match error {
+                    Chip8Error::UnknownOpcode(_) => warn!("{error} at {address:04X}"),
+                    _ => error!("{error} at {address:04X} ({:04X})", self.current_instruction.opcode())
+                }
+
+                (error, address)
+            })?;
+
+            if let Some(auto_speed) = &mut self.auto_speed {
+                auto_speed.observe(address, self.current_instruction.opcode());
             }
+        }
+
+        Ok(())
+    }
+
+    /// The most cycles `step_out` runs before giving up on the subroutine returning
+    pub const STEP_OUT_LIMIT: u32 = 1_000_000;
+
+    /// Runs until the subroutine being executed returns and pauses there, to skip over the rest of it while debugging.
+    /// Stops early if the program halts or waits for a key since it can't get any further, and does nothing outside
+    /// of a subroutine. Fails if an instruction does or the subroutine doesn't return within `STEP_OUT_LIMIT` cycles
+    pub fn step_out(&mut self) -> Result<(), Chip8Error> {
+        let depth = self.stack_ptr;
+
+        if depth == 0 {
+            self.paused = true;
+            return Ok(());
+        }
+
+        match self.run_while_debugging(Self::STEP_OUT_LIMIT, |chip8| chip8.stack_ptr < depth)? {
+            StepOutcome::CycleLimitReached => Err(Chip8Error::CycleLimitReached(Self::STEP_OUT_LIMIT)),
+            _ => Ok(())
+        }
+    }
+
+    /// Runs until the pc gets to `address` and pauses there, like a breakpoint that's only hit once. At least one
+    /// instruction runs, so starting at the address runs until it's reached again. Fails only if an instruction does
+    pub fn run_until(&mut self, address: u16, max_cycles: u32) -> Result<StepOutcome, Chip8Error> {
+        self.run_while_debugging(max_cycles, |chip8| chip8.pc == address as usize)
+    }
+
+    /// Runs until a `DXYN` executes and pauses after it, e.g. to skip a ROM's setup in tests without counting cycles.
+    /// Returns false if nothing was drawn within `max_cycles` or before the program halted or waited for a key.
+    /// Clearing the screen doesn't count as a draw
+    pub fn run_until_draw(&mut self, max_cycles: u32) -> Result<bool, Chip8Error> {
+        // Counted rather than looking at the current instruction, which stays the DXYN while waiting for the vertical blank
+        let draws = self.draws;
+        let outcome = self.run_while_debugging(max_cycles, |chip8| chip8.draws > draws)?;
+
+        Ok(outcome == StepOutcome::Reached)
+    }
+
+    /// Runs up to `max_cycles` instructions until `reached` holds after one of them and pauses, even if already paused.
+    /// Timers tick every `cycles_per_frame` instructions as if the game was running. Stops early if the program halts
+    /// or waits for a key since it can't get any further without input
+    fn run_while_debugging(&mut self, max_cycles: u32, reached: impl Fn(&Self) -> bool) -> Result<StepOutcome, Chip8Error> {
+        let mut result = Ok(StepOutcome::CycleLimitReached);
+
+        for cycle in 1..=max_cycles {
+            if let Err(error) = self.execute_cycle() {
+                result = Err(error);
+                break;
+            }
+
+            if reached(self) {
+                result = Ok(StepOutcome::Reached);
+                break;
+            } else if self.halted {
+                result = Ok(StepOutcome::Halted);
+                break;
+            } else if self.keyboard.awaiting_key_press {
+                result = Ok(StepOutcome::WaitingForKey);
+                break;
+            }
+
+            if cycle % self.cycles_per_frame.max(1) == 0 {
+                self.tick_timers();
+            }
+        }
+
+        self.paused = true;
+        self.refresh_overlays();
+
+        result
+    }
+
+    #[cfg(feature = "log")]
+    /// Logs every instruction executed at the trace level. Off by default since formatting them slows execution down
+    pub fn set_trace_instructions(&mut self, enabled: bool) {
+        self.trace_instructions = enabled;
+    }
+
+    /// Keeps track of where each `DXYN` draws from and to, for tools that show which memory sprites come from.
+    /// Off by default
+    pub fn set_record_draws(&mut self, enabled: bool) {
+        self.record_draws = enabled;
+
+        if !enabled {
+            self.last_draw = None;
+        }
+    }
+
+    /// The last sprite drawn since draws started being recorded, see `set_record_draws`.
+    /// None if nothing was drawn since then or the machine was reset
+    pub fn last_draw_info(&self) -> Option<DrawInfo> {
+        self.last_draw
+    }
+
+    /// Executes a whole frame's worth of instructions even while paused, stopping at the first one that fails
+    pub fn step_frame(&mut self) -> Result<(), Chip8Error> {
+        let result = (0..self.cycles_per_frame).try_for_each(|_| self.execute_cycle());
+        self.refresh_overlays();
+
+        result
+    }
+
+    /// Runs a single instruction like `cycle` and then draws the display if it changed, returning whether a frame was
+    /// presented. For hosts that own their loop and tick per instruction, they still have to call `tick_timers` 60 times
+    /// a second and pick how many instructions to run between. `run_cycles` and `step_frame` tick per frame instead,
+    /// running a frame's worth of instructions at once and leaving the drawing to `render`
+    pub fn tick_once(&mut self) -> Result<bool, Chip8Error> {
+        self.cycle()?;
+
+        if !self.display.is_dirty() {
+            return Ok(false);
+        }
+
+        self.display.render()?;
+
+        // The backend may skip presenting, e.g. while the surface is being reconfigured
+        Ok(!self.display.is_dirty())
+    }
+
+    /// Draws the display to the window if it changed since it was last rendered.
+    /// Executing instructions never renders by itself, the host decides when to present
+    pub fn render(&mut self) -> Result<(), Chip8Error> {
+        if self.display.is_dirty() {
+            self.display.render()?;
+        }
+
+        Ok(())
+    }
+
+    /// Shows the registers, I, PC and timers over the display
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
 
-            if self.display.is_dirty() {
-                self.display.render();
+        if !enabled {
+            self.display.set_overlay(None);
+        }
+    }
+
+    pub fn is_debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// The address of the next instruction to execute
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The opcode of the last instruction fetched, which ran from the address before the pc advanced past it.
+    /// 0000 before anything has run
+    pub fn current_opcode(&self) -> u16 {
+        self.current_instruction.opcode()
+    }
+
+    /// The last instruction fetched as assembly, see `current_opcode`
+    pub fn current_disassembly(&self) -> String {
+        asm::disassemble(&self.current_instruction)
+    }
+
+    pub fn i_register(&self) -> usize {
+        self.i_register
+    }
+
+    /// V0 to VF
+    pub fn registers(&self) -> &[u8; 0x10] {
+        &self.registers
+    }
+
+    /// Sets V0 to VF, panics past VF
+    #[cfg(feature = "testing")]
+    pub fn set_register(&mut self, register: usize, value: u8) {
+        self.registers[register] = value;
+    }
+
+    #[cfg(feature = "testing")]
+    pub fn set_i(&mut self, address: usize) {
+        self.i_register = address;
+    }
+
+    /// Moves execution to another address, the next instruction runs from there
+    #[cfg(feature = "testing")]
+    pub fn set_pc(&mut self, address: usize) {
+        self.pc = address;
+    }
+
+    /// All of RAM, including the font and the interpreter's reserved area
+    pub fn memory(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// The largest program that fits in this machine's memory after the interpreter's reserved bytes
+    pub fn max_program_size(&self) -> usize {
+        self.ram.len() - PROGRAM_START
+    }
+
+    /// Splits memory into the regions it's laid out in, in address order, e.g. to label a hex dump
+    pub fn memory_map(&self) -> Vec<MemoryRegion> {
+        let font_end = self.font_base + FONT_LEN;
+
+        [
+            (MemoryRegionKind::Reserved, 0..self.font_base),
+            (MemoryRegionKind::Font, self.font_base..font_end),
+            (MemoryRegionKind::Reserved, font_end..PROGRAM_START),
+            (MemoryRegionKind::Program, PROGRAM_START..self.ram.len())
+        ]
+        .into_iter()
+        .filter(|(_, range)| !range.is_empty())
+        .map(|(kind, range)| MemoryRegion { kind, range })
+        .collect()
+    }
+
+    /// Formats `len` bytes of memory starting at `start` as lines of `address: bytes  ascii`, with a header before
+    /// each region of the memory map. Lines break at region boundaries so no line mixes regions. Anything past the end
+    /// of memory is left out
+    pub fn hexdump(&self, start: u16, len: usize) -> String {
+        const BYTES_PER_LINE: usize = 16;
+
+        let start = start as usize;
+        let end = start.saturating_add(len).min(self.ram.len());
+        let regions = self.memory_map();
+
+        let mut dump = String::new();
+        let mut address = start;
+
+        while address < end {
+            let region = regions.iter().find(|region| region.range.contains(&address)).expect("the memory map covers all of memory");
+
+            if address == start || address == region.range.start {
+                dump.push_str(&format!("-- {:?} --\n", region.kind));
             }
+
+            let line_end = (address + BYTES_PER_LINE).min(region.range.end).min(end);
+            let bytes = &self.ram[address..line_end];
+
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+            let ascii: String = bytes.iter()
+                .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' {byte as char} else {'.'})
+                .collect();
+
+            dump.push_str(&format!("{address:04X}: {:<width$}  {ascii}\n", hex.join(" "), width = BYTES_PER_LINE * 3 - 1));
+
+            address = line_end;
+        }
+
+        dump
+    }
+
+    /// The return addresses of the subroutines currently being executed, innermost last
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.stack_ptr]
+    }
+
+    /// The call stack as a debugger shows it in a backtrace, the return address of the most recent call last
+    pub fn call_stack(&self) -> Vec<u16> {
+        self.stack().to_vec()
+    }
+
+    /// The machine's state as shown by the debug overlay
+    pub fn debug_text(&self) -> String {
+        let mut text = String::new();
+
+        for (row, registers) in self.registers.chunks(4).enumerate() {
+            let line: Vec<String> = registers.iter()
+                .enumerate()
+                .map(|(column, value)| format!("V{:X}:{value:02X}", row * 4 + column))
+                .collect();
+
+            text.push_str(&line.join(" "));
+            text.push('\n');
         }
+
+        text.push_str(&format!("I:{:04X} PC:{:04X}\n", self.i_register, self.pc));
+        text.push_str(&format!("DT:{:02X} ST:{:02X}", self.delay_timer, self.sound_timer));
+
+        text
+    }
+
+    /// The error that stopped the machine while the crash screen shows it, see `Chip8Config::crash_screen`. Cleared
+    /// once execution resumes
+    pub fn crash_error(&self) -> Option<&Chip8Error> {
+        self.crash.as_ref().map(|(error, _)| error)
+    }
+
+    /// The address of the instruction that raised `crash_error`
+    pub fn crash_address(&self) -> Option<usize> {
+        self.crash.as_ref().map(|&(_, address)| address)
+    }
+
+    /// Updates the debug overlay with the machine's current state and dims the screen while paused. It's only refreshed
+    /// after a batch of instructions since formatting the overlay after every instruction would be wasted work
+    fn refresh_overlays(&mut self) {
+        // Resuming dismisses the crash screen
+        if !self.paused && self.crash.take().is_some() && !self.debug_overlay {
+            self.display.set_overlay(None);
+        }
+
+        if let Some((_, address)) = self.crash {
+            // The error itself was logged when it happened, the screen only has room for where
+            self.display.set_overlay(Some(format!("CRASHED\nPC:{address:04X}\nOP:{:04X}", self.current_instruction.opcode())));
+        } else if self.debug_overlay {
+            self.display.set_overlay(Some(self.debug_text()));
+        }
+
+        self.display.set_dimmed(self.paused);
     }
     
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /// Sets the sound timer, starting the beep if it was stopped and stopping it if the timer is set to 0
+    pub fn set_sound_timer(&mut self, value: u8) {
+        let was_playing = self.sound_timer > 0;
+        self.sound_timer = value;
+
+        if !was_playing && self.sound_timer > 0 {
+            self.start_sound();
+        } else if was_playing && self.sound_timer == 0 {
+            self.stop_sound();
+        }
+    }
+
+    /// How many 60Hz frames have passed since the program was loaded, counted by `tick_timers`. Independent of wall time
+    /// and of how many instructions ran, so it can be used to time host features deterministically
+    pub fn frame_count(&self) -> u64 {
+        self.frame
+    }
+
     pub fn tick_timers(&mut self) {
+        self.frame += 1;
+        self.play_due_input();
+        self.keyboard.latch();
+
+        self.display.tick_frame();
+        self.waiting_for_vblank = false;
+        self.draws_this_frame = 0;
+
+        if let Some(new_speed) = self.auto_speed.as_mut().and_then(|auto_speed| auto_speed.end_frame(self.cycles_per_frame)) {
+            info!("Auto speed set {new_speed} cycles per frame");
+            self.cycles_per_frame = new_speed;
+        }
+
+        self.pace_frame();
+
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -356,19 +1468,184 @@ impl Chip8 {
             self.sound_timer -= 1;
 
             if self.sound_timer == 0 {
-                self.beep.pause();
+                self.stop_sound();
             }
         }
+
+        #[cfg(feature = "gif")]
+        if let Some(gif_recording) = &mut self.gif_recording {
+            gif_recording.tick(&self.display);
+        }
+    }
+
+    /// Replaces the built in beep with a callback invoked when the sound timer becomes non-zero
+    pub fn set_on_sound_start(&mut self, callback: impl FnMut() + 'static) {
+        self.on_sound_start = Some(Box::new(callback));
+    }
+
+    /// Replaces the built in beep with a callback invoked when the sound timer reaches zero
+    pub fn set_on_sound_stop(&mut self, callback: impl FnMut() + 'static) {
+        self.on_sound_stop = Some(Box::new(callback));
+    }
+
+    /// Gives opcodes the interpreter doesn't know (e.g. unused parts of `0NNN`) to the handler before failing with
+    /// `UnknownOpcode`, to prototype new instructions or host calls. The handler returns whether it handled the opcode
+    pub fn set_custom_handler(&mut self, handler: impl FnMut(&mut Chip8Context, u16) -> bool + 'static) {
+        self.custom_handler = Some(Box::new(handler));
+    }
+
+    #[cfg(feature = "audio")]
+    /// Sets the beep's volume between 0.0 and 1.0. Does nothing if no audio device is available
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(beep) = &mut self.beep {
+            beep.set_volume(volume);
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    /// Mutes or unmutes the beep without losing its volume. Does nothing if no audio device is available
+    pub fn set_muted(&mut self, muted: bool) {
+        if let Some(beep) = &mut self.beep {
+            beep.set_muted(muted);
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn is_muted(&self) -> bool {
+        self.beep.as_ref().is_none_or(|beep| beep.is_muted())
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn volume(&self) -> f32 {
+        self.beep.as_ref().map_or(0.0, |beep| beep.volume())
+    }
+
+    #[cfg(feature = "audio")]
+    /// The audio output the beep plays to, for hosts to play their own sounds alongside it. None if no audio device is
+    /// available, which is always the case for headless machines. The beep's volume and mute don't affect it
+    pub fn audio_mixer(&self) -> Option<&audio::AudioMixer> {
+        self.beep.as_ref().map(|beep| beep.mixer())
+    }
+
+    fn start_sound(&mut self) {
+        match &mut self.on_sound_start {
+            Some(callback) => callback(),
+            #[cfg(feature = "audio")]
+            None => if let Some(beep) = &self.beep {
+                beep.play();
+            },
+            #[cfg(not(feature = "audio"))]
+            None => {}
+        }
     }
 
+    fn stop_sound(&mut self) {
+        match &mut self.on_sound_stop {
+            Some(callback) => callback(),
+            #[cfg(feature = "audio")]
+            None => if let Some(beep) = &self.beep {
+                beep.pause();
+            },
+            #[cfg(not(feature = "audio"))]
+            None => {}
+        }
+    }
+
+    /// Presses a key, which also wakes the machine up if it halted. Key repeats of a held key are ignored
     pub fn on_key_down(&mut self, keycode: &VirtualKeyCode) {
-        self.keyboard.on_key_down(keycode);
+        if !self.keyboard.is_key_event_new(keycode) {
+            return;
+        }
+
+        // Pressing the key resumes a halted program, keys that aren't on the keypad leave it halted
+        if let Some(key) = self.keyboard.key_code(keycode) {
+            self.press_key(key);
+        }
     }
 
     pub fn on_key_up(&mut self, keycode: &VirtualKeyCode) {
-        self.keyboard.on_key_up(keycode);
+        if let Some(key) = self.keyboard.key_code(keycode) {
+            self.release_key(key);
+        }
+    }
+
+    /// Presses a key on the keypad (0x0 to 0xF) directly, for hosts that don't use winit's keys
+    pub fn press_key(&mut self, key: u8) {
+        self.record_input(key, true);
+        self.halted = false;
+        self.keyboard.press(key);
+    }
+
+    pub fn release_key(&mut self, key: u8) {
+        self.record_input(key, false);
+        self.keyboard.release(key);
+    }
+
+    /// Whether a key on the keypad (0x0 to 0xF) is held down right now
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.keyboard.is_pressed(key)
+    }
+
+    /// Starts recording every key press and release with the frame it happened on, replacing any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recording = Some((self.frame, Vec::new()));
+    }
+
+    /// Returns the input since `start_recording`, empty if nothing was being recorded
+    pub fn stop_recording(&mut self) -> replay::InputLog {
+        let events = self.recording.take().map(|(_, events)| events).unwrap_or_default();
+
+        replay::InputLog { events }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replays recorded input, each event is pressed or released when as many frames passed as when it was recorded.
+    /// Keys pressed by the user still go through while replaying
+    pub fn play_input_log(&mut self, log: replay::InputLog) {
+        self.playback = Some((self.frame, log.events.into()));
+        self.play_due_input();
+    }
+
+    /// Whether a replay still has input left to play
+    pub fn is_playing_input(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    fn record_input(&mut self, key: u8, pressed: bool) {
+        if let Some((start, events)) = &mut self.recording {
+            events.push(replay::InputEvent { frame: self.frame - *start, key, pressed });
+        }
+    }
+
+    /// Plays the events of the replay that are due on the current frame
+    fn play_due_input(&mut self) {
+        while let Some((start, events)) = &mut self.playback {
+            let elapsed = self.frame - *start;
+
+            match events.front() {
+                Some(event) if event.frame <= elapsed => {
+                    let event = events.pop_front().unwrap();
+
+                    if event.pressed {
+                        self.press_key(event.key);
+                    } else {
+                        self.release_key(event.key);
+                    }
+                },
+                Some(_) => break,
+                None => self.playback = None
+            }
+        }
     }
     
+    /// Releases every key, see `Keyboard::clear`
+    pub fn clear_keys(&mut self) {
+        self.keyboard.clear();
+    }
+
     /// For use with functions that make the chip 8 wait for a key press
     fn handle_await_keypress(&mut self) {
         self.registers[self.current_instruction.d2() as usize] = self.keyboard.get_last_key_pressed();
@@ -376,7 +1653,59 @@ impl Chip8 {
         self.keyboard.recieved_key_press = false;
     }
 
+    /// Turns vsync on or off, returning the present mode the display ended up using or None when headless
+    pub fn set_vsync(&mut self, vsync: bool) -> Option<display::PresentMode> {
+        self.display.set_vsync(vsync)
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        self.display.is_vsync()
+    }
+
+    pub fn set_scanlines(&mut self, enabled: bool) {
+        self.display.set_scanlines(enabled);
+    }
+
+    pub fn set_grid(&mut self, enabled: bool) {
+        self.display.set_grid(enabled);
+    }
+
+    /// Renders the current frame offscreen and returns it as RGBA pixels, see `Display::read_pixels`
+    pub fn capture_frame(&mut self) -> Result<Vec<u8>, Chip8Error> {
+        self.display.read_pixels()
+    }
+
+    /// Starts recording the display into an animated GIF at `path`, finishing any recording in progress first. The
+    /// screen is captured on every timer tick with the current palette, frames that didn't change are merged into
+    /// one that lasts longer. Works headless too since it reads the pixels rather than what was rendered
+    #[cfg(feature = "gif")]
+    pub fn start_gif_recording(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), Chip8Error> {
+        self.stop_gif_recording()?;
+        self.gif_recording = Some(GifRecorder::new(path.as_ref(), &self.display)?);
+
+        Ok(())
+    }
+
+    /// Writes the last frame and closes the GIF, does nothing if there's no recording. Fails if any frame couldn't
+    /// be written, the file is left incomplete then
+    #[cfg(feature = "gif")]
+    pub fn stop_gif_recording(&mut self) -> Result<(), Chip8Error> {
+        self.gif_recording.take().map_or(Ok(()), GifRecorder::finish)
+    }
+
+    #[cfg(feature = "gif")]
+    pub fn is_recording_gif(&self) -> bool {
+        self.gif_recording.is_some()
+    }
+
     pub fn handle_resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
         self.display.resize(new_size);
     }
-}
\ No newline at end of file
+}
+
+/// The same as `Chip8::headless`
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::headless()
+    }
+}
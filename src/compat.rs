@@ -0,0 +1,178 @@
+//! Runs a ROM headless and looks for behavior that works differently between interpreters or that this one can't
+//! handle, to help figure out why a game misbehaves and which quirks it needs
+
+use std::fmt;
+use crate::{Chip8, Chip8Config, Chip8Error, instruction::Instruction};
+
+/// Where the COSMAC VIP kept its call stack and display buffer. Programs touching it were relying on a specific interpreter
+const RESERVED_MEMORY: usize = 0xEA0;
+
+/// Drawing more sprites than this in a single frame was impossible on the original hardware, so a game doing it
+/// probably expects the display_wait quirk to slow it down
+const EXCESSIVE_DRAWS_PER_FRAME: u32 = 20;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    pub cycles_run: u32,
+    /// The address and value of every instruction this interpreter ignores
+    pub unknown_opcodes: Vec<(usize, u16)>,
+    /// The addresses of instructions that accessed the interpreter's reserved memory at 0xEA0 and up
+    pub reserved_memory_accesses: Vec<usize>,
+    /// The address of the instruction that stopped the run by failing and its error. Unknown opcodes don't stop it
+    pub fault: Option<(usize, Chip8Error)>,
+    /// How many times 8XY6/8XYE ran with X != Y, which gives different results depending on the shift_uses_vy quirk
+    pub shift_quirk_uses: u32,
+    /// How many times BNNN ran with VX != V0, which jumps elsewhere depending on the jump_uses_vx quirk
+    pub jump_quirk_uses: u32,
+    pub max_draws_per_frame: u32,
+    /// The program jumped to itself before the cycles ran out
+    pub halted: bool,
+    /// The program waited for a key press before the cycles ran out. No keys are pressed during the run
+    pub waiting_for_key: bool
+}
+
+impl CompatReport {
+    /// Describes every finding in a sentence, empty if nothing looks risky
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for (address, opcode) in &self.unknown_opcodes {
+            warnings.push(format!("{opcode:04X} at {address:04X} isn't a supported instruction and was skipped"));
+        }
+
+        for address in &self.reserved_memory_accesses {
+            warnings.push(format!("The instruction at {address:04X} accesses memory the original interpreter reserved for itself"));
+        }
+
+        if let Some((address, error)) = &self.fault {
+            warnings.push(format!("Stopped at {address:04X} since it {error}"));
+        }
+
+        if self.shift_quirk_uses > 0 {
+            warnings.push(format!("Shifted with X != Y {} times, try toggling the shift_uses_vy quirk", self.shift_quirk_uses));
+        }
+
+        if self.jump_quirk_uses > 0 {
+            warnings.push(format!("Jumped with BNNN while VX != V0 {} times, try toggling the jump_uses_vx quirk", self.jump_quirk_uses));
+        }
+
+        if self.max_draws_per_frame > EXCESSIVE_DRAWS_PER_FRAME {
+            warnings.push(format!("Drew up to {} sprites in a single frame, try enabling the display_wait quirk", self.max_draws_per_frame));
+        }
+
+        warnings
+    }
+}
+
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ran {} cycles", self.cycles_run)?;
+
+        if self.halted {
+            write!(f, " until the program halted")?;
+        } else if self.waiting_for_key {
+            write!(f, " until the program waited for a key press")?;
+        }
+
+        writeln!(f)?;
+
+        let warnings = self.warnings();
+
+        if warnings.is_empty() {
+            writeln!(f, "Nothing risky found")?;
+        }
+
+        for warning in warnings {
+            writeln!(f, "- {warning}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a program for up to `cycles` instructions without a window and reports anything that could make it behave
+/// differently than intended. The timers tick every `config.cycles_per_frame` instructions as they would at 60Hz.
+/// Fails only if the config is invalid or the program can't be loaded
+pub fn check_rom(program: Vec<u8>, config: Chip8Config, cycles: u32) -> Result<CompatReport, Chip8Error> {
+    let mut chip8 = Chip8::headless_from_config(config)?;
+    chip8.load_program(program)?;
+
+    let mut report = CompatReport::default();
+    let mut frame_cycles = 0;
+    let mut frame_draws = 0;
+
+    while report.cycles_run < cycles {
+        // Paused by an error with halt_on_error, nothing runs until the host resumes it
+        if chip8.paused {
+            break;
+        }
+
+        if chip8.is_halted() || chip8.is_waiting_for_key() {
+            report.halted = chip8.is_halted();
+            report.waiting_for_key = chip8.is_waiting_for_key();
+            break;
+        }
+
+        // Drawing with display_wait or past the draw limit ends the frame early
+        if frame_cycles >= chip8.cycles_per_frame() || chip8.is_waiting_for_vblank() {
+            chip8.tick_timers();
+            report.max_draws_per_frame = report.max_draws_per_frame.max(frame_draws);
+            frame_cycles = 0;
+            frame_draws = 0;
+        }
+
+        let pc = chip8.pc();
+        let memory = chip8.memory();
+
+        if pc + 1 >= memory.len() && !chip8.quirks().pc_wrap {
+            report.fault = Some((pc, Chip8Error::OutOfBoundsMemory(memory.len())));
+            break;
+        }
+
+        let instruction = Instruction::from_bytes(memory[pc], memory[(pc + 1) % memory.len()]);
+        let registers = chip8.registers();
+
+        // The memory the instruction reads or writes, as a start address and a length
+        let accessed = match (instruction.d1(), instruction.d3(), instruction.d4()) {
+            (0xD, _, n) => Some((chip8.i_register(), n as usize)),
+            (0xF, 0x3, 0x3) => Some((chip8.i_register(), 3)),
+            (0xF, 0x5 | 0x6, 0x5) => Some((chip8.i_register(), instruction.x() + 1)),
+            _ => None
+        };
+
+        if accessed.is_some_and(|(start, length)| length > 0 && start + length > RESERVED_MEMORY) && !report.reserved_memory_accesses.contains(&pc) {
+            report.reserved_memory_accesses.push(pc);
+        }
+
+        match (instruction.d1(), instruction.d4()) {
+            (8, 0x6 | 0xE) if instruction.x() != instruction.y() => report.shift_quirk_uses += 1,
+            (0xB, _) if registers[instruction.x()] != registers[0] => report.jump_quirk_uses += 1,
+            (0xD, _) => frame_draws += 1,
+            _ => {}
+        }
+
+        let result = chip8.run_cycles(1);
+        // A failing instruction still ran
+        let executed = result.as_ref().map_or(1, |&executed| executed);
+        report.cycles_run += executed;
+        frame_cycles += executed;
+
+        match result {
+            // Unknown opcodes are skipped over, so the program can keep going
+            Err(Chip8Error::UnknownOpcode(opcode)) => {
+                if !report.unknown_opcodes.contains(&(pc, opcode)) {
+                    report.unknown_opcodes.push((pc, opcode));
+                }
+            },
+            Err(error) => {
+                report.fault = Some((pc, error));
+                break;
+            },
+            Ok(_) => {}
+        }
+    }
+
+    report.max_draws_per_frame = report.max_draws_per_frame.max(frame_draws);
+
+    Ok(report)
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use winit::event::VirtualKeyCode;
+use crate::{chip8, keyboard, quirks::Quirks, renderer::GpuConfig};
+
+/// Everything that can be configured when creating a `Chip8`.
+/// Start from `Chip8Config::default()`, which matches the emulator's standard behavior, and override what's needed
+#[derive(Clone, Debug)]
+pub struct Chip8Config {
+    /// How many instructions run every 60Hz frame
+    pub cycles_per_frame: u32,
+    /// Which interpreter's behavior to follow where they disagree
+    pub quirks: Quirks,
+    /// The address the hex digit sprites are loaded at and `FX29` points into. Must leave room for the `FONT_LEN` byte font
+    /// below `PROGRAM_START`
+    pub font_base: usize,
+    /// How many bytes of memory the machine has, `RAM_SIZE` (4KB) like the COSMAC VIP by default. Variants like XO-CHIP
+    /// have up to 64KB. Must be larger than `PROGRAM_START`
+    pub ram_size: usize,
+    /// Stop executing instructions when the program jumps to itself (`1NNN` with NNN being its own address), which most
+    /// ROMs do to halt. Execution resumes when a key is pressed
+    pub detect_halt: bool,
+    /// Pause when an instruction fails, leaving the machine as the instruction left it. The error is still returned
+    /// by whatever ran the instruction, execution resumes when `paused` is cleared
+    pub halt_on_error: bool,
+    /// With `halt_on_error`, shows a crash screen over the dimmed display when an instruction fails, so a fault can't be
+    /// mistaken for the game hanging. It's dismissed when execution resumes, see `Chip8::crash_error`
+    pub crash_screen: bool,
+    /// Start paused, and pause again whenever a ROM is reloaded, so nothing runs until `paused` is cleared. Useful to
+    /// step through a program from its first instruction
+    pub start_paused: bool,
+    /// Seeds the random number generator used by `CXNN` so runs can be reproduced. Random when None
+    pub rng_seed: Option<u64>,
+    /// `EX9E`/`EXA1` see the keys that were down at the last timer tick instead of the ones down right now, so input
+    /// only changes once per frame like on interpreters that scanned the keypad during the vertical blank. Makes the
+    /// timing of input deterministic
+    pub latch_keys: bool,
+    /// Which key on the keyboard presses which CHIP-8 key (0x0 - 0xF)
+    pub key_map: HashMap<VirtualKeyCode, u8>,
+    /// The color of pixels that are on as sRGB
+    pub foreground: [u8; 3],
+    /// The color of pixels that are off as sRGB
+    pub background: [u8; 3],
+    /// The beep's volume between 0.0 and 1.0
+    pub volume: f32,
+    /// Which GPU renders the display
+    pub gpu: GpuConfig
+}
+
+impl Default for Chip8Config {
+    fn default() -> Self {
+        Self {
+            cycles_per_frame: 60,
+            quirks: Quirks::default(),
+            font_base: chip8::FONT_BASE,
+            ram_size: chip8::RAM_SIZE,
+            detect_halt: true,
+            halt_on_error: false,
+            crash_screen: false,
+            start_paused: false,
+            rng_seed: None,
+            latch_keys: false,
+            key_map: keyboard::Keyboard::default_key_map(),
+            foreground: [0xFF, 0xFF, 0xFF],
+            background: [0x00, 0x00, 0x00],
+            volume: 1.0,
+            gpu: GpuConfig::default()
+        }
+    }
+}
@@ -1,269 +1,619 @@
-use wgpu::util::DeviceExt;
-use pollster;
-use bytemuck::{Pod, Zeroable};
-
-const PIXEL_VERTICES: [f32; 12] = [
-  // first triangle: top left -> bottom left -> top right
-  0.0, 1.0,
-  0.0, 0.0,
-  1.0, 1.0,
-  // second triangle: bottom left -> bottom right -> top right
-  0.0, 0.0,
-  1.0, 0.0,
-  1.0, 1.0
-];
-
-
-#[repr(C)]
-#[derive(Clone, Copy, Pod, Zeroable, Debug)]
-struct Instance {
-    pos: [f32; 2],
-    on: f32
+use std::sync::Arc;
+use crate::{error::Chip8Error, renderer::{GpuConfig, GpuContext, WgpuDisplay}};
+
+/// The state of every pixel on the screen, one bit per pixel. Each row is a u128 whose most significant bit is the
+/// leftmost pixel, so pixel (x, y) is `pixels[y] >> (127 - x) & 1`. Resolutions smaller than the maximum only use
+/// the top left of it
+pub type Pixels = [u128; Display::MAX_HEIGHT];
+
+/// The four colors pixels are shown in, given as sRGB and indexed by `color_index`. 0 is the background and 1 the
+/// foreground, 2 and 3 only show up where the second plane has pixels on
+pub type Palette = [[u8; 3]; 4];
+
+/// Which palette color a pixel is shown in. Each plane contributes a bit of the index, the first plane the low one,
+/// so a display that only draws to the first plane shows the usual two colors
+pub fn color_index(first_plane: &Pixels, second_plane: &Pixels, x: usize, y: usize) -> usize {
+    let bit = |plane: &Pixels| (plane[y] >> (Display::MAX_WIDTH - 1 - x) & 1) as usize;
+
+    bit(first_plane) | bit(second_plane) << 1
 }
 
-impl Instance {
-    fn desc() -> wgpu::VertexBufferLayout<'static> {
-        use std::mem;
-
-        wgpu::VertexBufferLayout { 
-            array_stride: mem::size_of::<Instance>() as u64, 
-            step_mode: wgpu::VertexStepMode::Instance, 
-            attributes: &[
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x2,
-                    offset: 0,
-                    shader_location: 1
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32,
-                    offset: mem::size_of::<[f32; 2]>() as u64,
-                    shader_location: 2
-                }
-            ]
-        }
+/// Shows the display's pixels somewhere. `WgpuDisplay` draws them to a window with wgpu and `NullDisplay` drops them,
+/// other frontends (e.g. a terminal) can implement it to reuse everything else. Only `render` is required, the rest
+/// are display options that backends without them can ignore
+pub trait DisplayBackend {
+    /// Shows the pixels, returning whether the frame was actually presented
+    fn render(&mut self, pixels: &Pixels) -> Result<bool, Chip8Error>;
+
+    /// Whether the pixels aren't shown anywhere
+    fn is_headless(&self) -> bool {
+        false
+    }
+
+    /// Only the top left `width` x `height` of the pixels are shown from now on
+    fn set_resolution(&mut self, _width: usize, _height: usize) {}
+
+    fn set_scanlines(&mut self, _enabled: bool) {}
+
+    fn set_grid(&mut self, _enabled: bool) {}
+
+    /// Sets the colors of pixels that are on and off, given as sRGB
+    fn set_colors(&mut self, _foreground: [u8; 3], _background: [u8; 3]) {}
+
+    /// Sets all four colors, see `Palette`. Backends that only show the first plane just need the first two
+    fn set_palette(&mut self, palette: Palette) {
+        self.set_colors(palette[1], palette[0]);
+    }
+
+    /// The pixels of the second plane, shown together with the ones passed to `render` in the palette's colors
+    fn set_second_plane(&mut self, _pixels: &Pixels) {}
+
+    fn set_dimmed(&mut self, _dimmed: bool) {}
+
+    fn set_overlay(&mut self, _text: Option<&str>) {}
+
+    /// Shows how strongly each pixel is tinted after a collision, a byte per pixel laid out like `Pixels`
+    #[cfg(feature = "debug")]
+    fn upload_ghosts(&mut self, _intensities: &[u8]) {}
+
+    /// Returns how frames ended up being presented, None if the backend doesn't present to a surface
+    fn set_vsync(&mut self, _vsync: bool) -> Option<PresentMode> {
+        None
+    }
+
+    fn is_vsync(&self) -> bool {
+        false
+    }
+
+    fn resize(&mut self, _new_size: &winit::dpi::PhysicalSize<u32>) {}
+
+    fn texture_uploads(&self) -> u64 {
+        0
+    }
+
+    /// The size of the frames returned by `read_pixels`
+    fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        winit::dpi::PhysicalSize::new(0, 0)
+    }
+
+    /// The last frame as RGBA pixels, empty if the backend can't capture frames
+    fn read_pixels(&mut self, _pixels: &Pixels) -> Result<Vec<u8>, Chip8Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// How a backend presents frames to its surface, see `DisplayBackend::set_vsync`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Waits for the vertical blank
+    Vsync,
+    /// Replaces the frame waiting for the vertical blank without blocking, doesn't tear
+    Mailbox,
+    /// Presents right away, which may tear
+    Immediate
+}
+
+/// A backend that shows nothing, used by headless displays
+pub struct NullDisplay;
+
+impl DisplayBackend for NullDisplay {
+    fn render(&mut self, _pixels: &Pixels) -> Result<bool, Chip8Error> {
+        Ok(true)
+    }
+
+    fn is_headless(&self) -> bool {
+        true
     }
 }
 
 pub struct Display {
-    pixels: [[bool; Display::WIDTH]; Display::HEIGHT], // Each column (Display::HEIGHT) will have Display::WIDTH rows in it
+    pixels: Pixels,
+    second_plane: Pixels, // XO-CHIP's second bitplane, see `set_second_plane`
+    width: usize,
+    height: usize,
     dirty: bool,
-    surface: wgpu::Surface,
-    surface_config: wgpu::SurfaceConfiguration,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    instance_buffer: wgpu::Buffer,
+    dimmed: bool,
+    palette: Palette,
+    wrap_x: bool, // Whether sprites wrap around the left and right edges or are clipped at them, see `set_wrapping`
+    wrap_y: bool,
+    color_cycle: Option<f32>, // How many times per second the foreground goes around the color wheel, None when off
+    frames: u64, // Counts calls to `tick_frame`, drives the color cycle
+    #[cfg(feature = "debug")]
+    ghosts: Option<Vec<u8>>, // How strongly each pixel is tinted, laid out like `Pixels` with a byte per pixel. None when off
+    overlay: Option<String>, // Text drawn over the pixels, see `set_overlay`
+    backend: Box<dyn DisplayBackend>
 }
 
 impl Display {
+    /// The default resolution
     pub const WIDTH: usize = 64;
     pub const HEIGHT: usize = 32;
 
-    pub fn new(window: &winit::window::Window) -> Self {
-        let wgpu_instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default()
-        });
-
-        let surface = unsafe { wgpu_instance.create_surface(window) }.unwrap();
-
-        let adapter = pollster::block_on(wgpu_instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::LowPower,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface)
-        })).unwrap();
-
-        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-            label: Some("Chip 8 device and queue"),
-            features: wgpu::Features::empty(),
-            limits: wgpu::Limits::default()
-        }, None)).unwrap();
-
-        let surface_caps = surface.get_capabilities(&adapter);
-
-        let window_size = window.inner_size();
-
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_caps.formats[0],
-            width: window_size.width,
-            height: window_size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![]
-        };
+    /// White on black, with the colors of Octo's second plane for 2 and 3
+    pub const DEFAULT_PALETTE: Palette = [[0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF], [0xFF, 0x66, 0x00], [0x66, 0x22, 0x00]];
+
+    /// The largest resolution the display supports, used by hires mode
+    pub const MAX_WIDTH: usize = 128;
+    pub const MAX_HEIGHT: usize = 64;
 
-        surface.configure(&device, &surface_config);
-
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Pixel vertex buffer"),
-            contents: bytemuck::bytes_of(&PIXEL_VERTICES),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST
-        });
-
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Pixel instance buffer"),
-            size: (std::mem::size_of::<Instance>() * Self::WIDTH * Self::HEIGHT) as u64,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
-            mapped_at_creation: false
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Chip8 pipeline layout"),
-            bind_group_layouts: &[],
-            push_constant_ranges: &[]
-        });
-
-        let shader_src = wgpu::include_wgsl!("./shaders.wgsl");
-        let shader_module = device.create_shader_module(shader_src);
-
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Chip 8 pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[
-                    wgpu::VertexBufferLayout{
-                    array_stride: 2 * 4,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![0 => Float32x2]
-                }, Instance::desc()]
-            },
-            primitive: wgpu::PrimitiveState { 
-                topology: wgpu::PrimitiveTopology::TriangleList, 
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw, 
-                cull_mode: Some(wgpu::Face::Back), 
-                unclipped_depth: false, 
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module, 
-                entry_point: "fs_main", 
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL
-                })] 
-            }),
-            multiview: None
-        });
-
-        Display { 
-            pixels: [[false; Display::WIDTH]; Display::HEIGHT],
+    /// Draws to `window` on a GPU picked by `gpu_config`, failing if none can be set up
+    pub fn new(window: &winit::window::Window, gpu_config: &GpuConfig) -> Result<Self, Chip8Error> {
+        Ok(Self::with_backend(Box::new(WgpuDisplay::new(window, gpu_config)?)))
+    }
+
+    /// Draws to `window` with a GPU device shared with other displays, see `GpuContext`
+    pub fn with_context(context: Arc<GpuContext>, window: &winit::window::Window) -> Result<Self, Chip8Error> {
+        Ok(Self::with_backend(Box::new(WgpuDisplay::with_context(context, window)?)))
+    }
+
+    /// A display that keeps track of its pixels without a window or GPU, rendering does nothing.
+    /// Useful for tests, benchmarks and tools that only care about the emulation
+    pub fn headless() -> Self {
+        Self::with_backend(Box::new(NullDisplay))
+    }
+
+    /// A display shown by any backend, see `DisplayBackend`
+    pub fn with_backend(backend: Box<dyn DisplayBackend>) -> Self {
+        Display {
+            pixels: [0; Display::MAX_HEIGHT],
+            second_plane: [0; Display::MAX_HEIGHT],
+            width: Display::WIDTH,
+            height: Display::HEIGHT,
             dirty: false,
-            surface,
-            surface_config,
-            device,
-            queue,
-            pipeline,
-            instance_buffer,
-            vertex_buffer,
+            dimmed: false,
+            palette: Display::DEFAULT_PALETTE,
+            wrap_x: true,
+            wrap_y: true,
+            color_cycle: None,
+            frames: 0,
+            #[cfg(feature = "debug")]
+            ghosts: None,
+            overlay: None,
+            backend
         }
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.backend.is_headless()
+    }
+
+    /// The window size needed to show every CHIP-8 pixel as a `scale` x `scale` square
+    pub fn window_size(scale: u32) -> winit::dpi::PhysicalSize<u32> {
+        winit::dpi::PhysicalSize::new(Display::WIDTH as u32 * scale, Display::HEIGHT as u32 * scale)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Switches to another resolution, clearing the screen. Panics if it's larger than `MAX_WIDTH` x `MAX_HEIGHT`
+    pub fn set_resolution(&mut self, width: usize, height: usize) {
+        assert!(width > 0 && width <= Display::MAX_WIDTH && height > 0 && height <= Display::MAX_HEIGHT,
+            "{width}x{height} isn't a supported resolution");
+
+        self.width = width;
+        self.height = height;
+
+        self.backend.set_resolution(width, height);
+
+        self.clear_screen();
+    }
+
     /// Dirties the display and schedules it for redraw
     pub fn dirty(&mut self) {
         self.dirty = true;
     }
 
+    /// Whether the pixels changed since the last call to `render`
     pub fn is_dirty(&self) -> bool {
         self.dirty
     }
 
     pub fn clear_screen(&mut self) {
-        self.pixels = [[false; Display::WIDTH]; Display::HEIGHT];
+        self.pixels = [0; Display::MAX_HEIGHT];
+        self.second_plane = [0; Display::MAX_HEIGHT];
+        self.dirty();
+    }
+
+    /// The bit of a row that holds the pixel in column x
+    fn column_mask(&self, x: usize) -> u128 {
+        1 << (Display::MAX_WIDTH - 1 - x % self.width)
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y % self.height] & self.column_mask(x) != 0
+    }
+
+    /// Turns a single pixel on or off. The display is only dirtied if the pixel actually changed
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if self.get_pixel(x, y) != on {
+            self.pixels[y % self.height] ^= self.column_mask(x);
+            self.dirty();
+        }
+    }
+
+    /// Replaces every pixel with the given ones, indexed as `framebuffer[y][x]`. Useful to show screens drawn by the host
+    /// such as a splash screen. Switches back to the default resolution if another one was in use
+    pub fn set_framebuffer(&mut self, framebuffer: &[[bool; Display::WIDTH]; Display::HEIGHT]) {
+        if (self.width, self.height) != (Display::WIDTH, Display::HEIGHT) {
+            self.set_resolution(Display::WIDTH, Display::HEIGHT);
+        }
+
+        for (row, pixels) in self.pixels.iter_mut().zip(framebuffer) {
+            *row = Self::framebuffer_row(pixels);
+        }
+
+        self.second_plane = [0; Display::MAX_HEIGHT];
+        self.dirty();
+    }
+
+    /// Counts the pixels that differ from the given ones, indexed as `other[y][x]`. Pixels that are on outside of the
+    /// default resolution while another one is in use count as differing
+    pub fn diff(&self, other: &[[bool; Display::WIDTH]; Display::HEIGHT]) -> usize {
+        self.pixels.iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let other_row = other.get(y).map_or(0, Self::framebuffer_row);
+
+                (row ^ other_row).count_ones() as usize
+            })
+            .sum()
+    }
+
+    /// Packs a row of a framebuffer into a row of `Pixels`
+    fn framebuffer_row(pixels: &[bool; Display::WIDTH]) -> u128 {
+        pixels.iter()
+            .enumerate()
+            .filter(|(_, &on)| on)
+            .fold(0, |row, (x, _)| row | 1 << (Display::MAX_WIDTH - 1 - x))
+    }
+
+    /// Renders the screen as text, one line per row with `#` for pixels that are on and `.` for those that are off
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::with_capacity((self.width + 1) * self.height);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                ascii.push(if self.get_pixel(x, y) {'#'} else {'.'});
+            }
+
+            ascii.push('\n');
+        }
+
+        ascii
+    }
+
+    /// The screen packed 8 pixels to a byte, row by row from the top left with the leftmost pixel in the most significant
+    /// bit. 256 bytes in lowres and 1024 in hires, compact enough to stream to a remote viewer or store every frame
+    pub fn to_packed_bits(&self) -> Vec<u8> {
+        let bytes_per_row = self.width / 8;
+
+        self.pixels[..self.height]
+            .iter()
+            .flat_map(|row| (0..bytes_per_row).map(move |byte| (row >> (Display::MAX_WIDTH - 8 * (byte + 1))) as u8))
+            .collect()
+    }
+
+    /// Replaces every pixel with ones packed by `to_packed_bits`, switching to the resolution they were packed at.
+    /// Fails if there are as many bytes as neither resolution has, leaving the screen untouched
+    pub fn set_packed_bits(&mut self, bits: &[u8]) -> Result<(), Chip8Error> {
+        let (width, height) = [(Display::WIDTH, Display::HEIGHT), (Display::MAX_WIDTH, Display::MAX_HEIGHT)]
+            .into_iter()
+            .find(|(width, height)| width * height / 8 == bits.len())
+            .ok_or(Chip8Error::PackedDisplaySize(bits.len()))?;
+
+        if (self.width, self.height) != (width, height) {
+            self.set_resolution(width, height);
+        }
+
+        for (row, bytes) in self.pixels.iter_mut().zip(bits.chunks(width / 8)) {
+            *row = bytes.iter()
+                .enumerate()
+                .fold(0, |row, (byte, &bits)| row | (bits as u128) << (Display::MAX_WIDTH - 8 * (byte + 1)));
+        }
+
+        self.second_plane = [0; Display::MAX_HEIGHT];
         self.dirty();
+
+        Ok(())
+    }
+
+    /// Sets whether the parts of sprites that go past the right and bottom edges wrap around to the opposite edge
+    /// or are clipped. Where a sprite starts always wraps. Both wrap by default
+    pub fn set_wrapping(&mut self, wrap_x: bool, wrap_y: bool) {
+        self.wrap_x = wrap_x;
+        self.wrap_y = wrap_y;
+    }
+
+    /// Places a byte of a sprite at column x of a row, wrapping whatever goes past the right edge around to the left
+    /// unless horizontal wrapping is off
+    fn sprite_row(&self, byte: u8, x: usize) -> u128 {
+        let x = x % self.width;
+        let byte = (byte as u128) << (Display::MAX_WIDTH - 8);
+
+        // Shifting left by the distance to the right edge brings the bits that went past it back to the left.
+        // Both shifts can push bits outside of the display's width when it's smaller than a row, those are masked off
+        let wrapped = if self.wrap_x {byte.checked_shl((self.width - x) as u32).unwrap_or(0)} else {0};
+        let visible = !u128::MAX.checked_shr(self.width as u32).unwrap_or(0);
+
+        (byte >> x | wrapped) & visible
     }
 
+    /// XORs a sprite onto the screen, one byte per row, wrapping around the edges or clipping at them as set by
+    /// `set_wrapping`. Returns whether any pixel that was on got turned off
     pub fn draw(&mut self, starting_x: u8, starting_y: u8, memory: &[u8]) -> bool {
-        let mut pixel_turned_off = false;
+        let mut pixels_turned_off = 0;
+        let starting_y = starting_y as usize % self.height;
 
         for (byte_number, block) in memory.iter().enumerate() {
-            let y = (starting_y as usize + byte_number) % Display::HEIGHT;
+            let y = starting_y + byte_number;
 
-            for bit_number in 0..8 {
-                let x = (starting_x as usize + bit_number) % Display::WIDTH;
-                let current_pixel = self.pixels[y][x] as u8;
+            let y = match (y < self.height, self.wrap_y) {
+                (true, _) => y,
+                (false, true) => y % self.height,
+                (false, false) => break
+            };
+            let sprite_row = self.sprite_row(*block, starting_x as usize);
 
-                let current_bit = (block >> (7 - bit_number)) & 1;
-                let new_pixel = current_bit ^ current_pixel;
+            let turned_off = self.pixels[y] & sprite_row;
+            pixels_turned_off += turned_off.count_ones();
 
-                self.pixels[y][x] = new_pixel != 0;
+            #[cfg(feature = "debug")]
+            self.add_ghosts(y, turned_off);
 
-                pixel_turned_off = current_pixel == 1 && new_pixel == 0;
+            self.pixels[y] ^= sprite_row;
+        }
+
+        self.dirty();
+        pixels_turned_off > 0
+    }
+
+    /// Moves every pixel down by `rows`, the rows that scroll in at the top are blank
+    pub fn scroll_down(&mut self, rows: usize) {
+        let rows = rows.min(self.height);
+
+        self.pixels.copy_within(0..self.height - rows, rows);
+        self.pixels[..rows].fill(0);
+        self.dirty();
+    }
+
+    /// Moves every pixel right by `columns`, the columns that scroll in on the left are blank
+    pub fn scroll_right(&mut self, columns: usize) {
+        let visible = !u128::MAX.checked_shr(self.width as u32).unwrap_or(0);
+
+        for row in &mut self.pixels[..self.height] {
+            *row = row.checked_shr(columns as u32).unwrap_or(0) & visible;
+        }
+
+        self.dirty();
+    }
+
+    /// Moves every pixel left by `columns`, the columns that scroll in on the right are blank
+    pub fn scroll_left(&mut self, columns: usize) {
+        // Rows only have bits within the display's width, so nothing comes in from past the right edge
+        for row in &mut self.pixels[..self.height] {
+            *row = row.checked_shl(columns as u32).unwrap_or(0);
+        }
+
+        self.dirty();
+    }
+
+    /// Darkens the bottom of every row of pixels to imitate a CRT
+    pub fn set_scanlines(&mut self, enabled: bool) {
+        self.backend.set_scanlines(enabled);
+        self.dirty();
+    }
+
+    /// Darkens the edges of every pixel so they can be told apart
+    pub fn set_grid(&mut self, enabled: bool) {
+        self.backend.set_grid(enabled);
+        self.dirty();
+    }
+
+    /// Sets the colors of pixels that are on and off, given as sRGB. The second plane's colors are kept
+    pub fn set_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) {
+        self.palette[1] = foreground;
+        self.palette[0] = background;
+        self.apply_colors();
+    }
+
+    /// Sets all four colors at once, see `Palette`
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.apply_colors();
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Replaces the pixels of the second plane, laid out like the first. Pixels on in it are shown in palette colors 2
+    /// and 3. The interpreter doesn't draw to it since the XO-CHIP plane opcodes aren't supported, it's for hosts and
+    /// tools that want to show more than two colors
+    pub fn set_second_plane(&mut self, pixels: &Pixels) {
+        self.second_plane = *pixels;
+        self.dirty();
+    }
+
+    /// The palette index of the pixel at (x, y), see `color_index`
+    pub fn color_index(&self, x: usize, y: usize) -> usize {
+        color_index(&self.pixels, &self.second_plane, x % self.width, y % self.height)
+    }
+
+    /// Makes the foreground cycle through every hue, going around the color wheel `speed` times per second.
+    /// Purely cosmetic, the configured foreground comes back when it's disabled
+    pub fn set_color_cycle(&mut self, enabled: bool, speed: f32) {
+        self.color_cycle = enabled.then_some(speed);
+        self.apply_colors();
+    }
+
+    /// Advances anything animated by a 60Hz frame
+    pub fn tick_frame(&mut self) {
+        self.frames = self.frames.wrapping_add(1);
+
+        #[cfg(feature = "debug")]
+        self.fade_ghosts();
+
+        if self.color_cycle.is_some() {
+            self.apply_colors();
+        }
+    }
+
+    /// Tints pixels turned off by a collision red for a few frames, to see where sprites collided
+    #[cfg(feature = "debug")]
+    pub fn set_ghost_pixels(&mut self, enabled: bool) {
+        self.ghosts = enabled.then(|| vec![0; Display::MAX_WIDTH * Display::MAX_HEIGHT]);
+
+        self.backend.upload_ghosts(&[0; Display::MAX_WIDTH * Display::MAX_HEIGHT]);
+
+        self.dirty();
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn is_ghost_pixels(&self) -> bool {
+        self.ghosts.is_some()
+    }
+
+    /// Tints every pixel that's on in the row's mask
+    #[cfg(feature = "debug")]
+    fn add_ghosts(&mut self, y: usize, turned_off: u128) {
+        if let Some(ghosts) = &mut self.ghosts {
+            for x in 0..self.width {
+                if turned_off >> (Display::MAX_WIDTH - 1 - x) & 1 != 0 {
+                    ghosts[y * Display::MAX_WIDTH + x] = u8::MAX;
+                }
             }
         }
+    }
+
+    /// Fades the ghosts a bit, they disappear after `GHOST_FRAMES` calls
+    #[cfg(feature = "debug")]
+    fn fade_ghosts(&mut self) {
+        const GHOST_FRAMES: u8 = 15;
+
+        if let Some(ghosts) = &mut self.ghosts {
+            let mut faded = false;
+
+            for ghost in ghosts.iter_mut().filter(|ghost| **ghost > 0) {
+                *ghost = ghost.saturating_sub(u8::MAX / GHOST_FRAMES);
+                faded = true;
+            }
 
+            if faded {
+                self.dirty();
+            }
+        }
+    }
+
+    fn apply_colors(&mut self) {
+        let mut palette = self.palette;
+
+        if let Some(speed) = self.color_cycle {
+            palette[1] = Self::hue_to_rgb((self.frames as f64 * speed as f64 / 60.0).rem_euclid(1.0));
+        }
+
+        self.backend.set_palette(palette);
         self.dirty();
-        pixel_turned_off
     }
 
-    pub fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.surface_config.width = new_size.width;
-            self.surface_config.height = new_size.height;
+    /// The fully saturated color at a hue between 0.0 and 1.0, 0.0 being red
+    fn hue_to_rgb(hue: f64) -> [u8; 3] {
+        let channel = |offset: f64| {
+            let k = (offset + hue * 6.0) % 6.0;
+            let value = 1.0 - k.min(4.0 - k).clamp(0.0, 1.0);
+
+            (value * 255.0).round() as u8
+        };
+
+        [channel(5.0), channel(3.0), channel(1.0)]
+    }
+
+    /// Darkens the whole screen, used to show the emulator is paused. The display is only dirtied if this changed it
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        if dimmed == self.dimmed {
+            return;
+        }
+
+        self.backend.set_dimmed(dimmed);
+
+        self.dimmed = dimmed;
+        self.dirty();
+    }
+
+    pub fn is_dimmed(&self) -> bool {
+        self.dimmed
+    }
 
-            self.surface.configure(&self.device, &self.surface_config);
+    /// Draws text over the pixels, or removes it when None. The display is only dirtied if the text changed
+    pub fn set_overlay(&mut self, text: Option<String>) {
+        if text == self.overlay {
+            return;
+        }
 
+        self.backend.set_overlay(text.as_deref());
+
+        self.overlay = text;
+        self.dirty();
+    }
+
+    /// The text drawn over the pixels, see `set_overlay`
+    pub fn overlay(&self) -> Option<&str> {
+        self.overlay.as_deref()
+    }
+
+    /// See `WgpuDisplay::set_vsync`. Returns None when headless
+    pub fn set_vsync(&mut self, vsync: bool) -> Option<PresentMode> {
+        let present_mode = self.backend.set_vsync(vsync)?;
+        self.dirty();
+
+        Some(present_mode)
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        self.backend.is_vsync()
+    }
+
+    pub fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.backend.resize(new_size);
             self.dirty();
         }
     }
 
-    fn gen_instances(&self) -> [Instance; Display::WIDTH * Display::HEIGHT] {
-        let mut instances = [Instance {pos: [0.0, 0.0], on: 0.0}; Display::WIDTH * Display::HEIGHT];
+    /// Returns how many times the screen texture has been uploaded to the GPU. Useful to check redundant uploads are skipped
+    pub fn texture_uploads(&self) -> u64 {
+        self.backend.texture_uploads()
+    }
 
-        for y in 0..Display::HEIGHT {
-            for x in 0..Display::WIDTH {
-                
-                instances[Display::WIDTH * y + x] = Instance {
-                    pos: [x as f32, y as f32],
-                    on: self.pixels[y][x] as u32 as f32
-                };
-            }
+    /// Draws the pixels to the window, see `WgpuDisplay::render`. The display stays dirty if the frame couldn't be presented
+    pub fn render(&mut self) -> Result<(), Chip8Error> {
+        #[cfg(feature = "debug")]
+        if let Some(ghosts) = &self.ghosts {
+            self.backend.upload_ghosts(ghosts);
         }
 
-        instances
-    }
-
-    pub fn render(&mut self) {
-        let frame = self.surface.get_current_texture().unwrap();
-        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        self.queue.write_buffer(&self.instance_buffer, 0, bytemuck::bytes_of(&self.gen_instances()));
-
-        let mut command_encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Chip 8 command enconder")
-        });
-
-        {
-            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Chip 8 Render pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &frame_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true
-                    }
-                })],
-                depth_stencil_attachment: None
-            });
-            
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
-            render_pass.draw(0..6, 0..(Display::WIDTH as u32 * Display::HEIGHT as u32));
+        self.backend.set_second_plane(&self.second_plane);
+        let presented = self.backend.render(&self.pixels)?;
 
+        if presented {
             self.dirty = false;
         }
 
-        self.queue.submit(Some(command_encoder.finish()));
-        frame.present();
+        Ok(())
+    }
+
+    /// The size of the frames returned by `read_pixels`, zero when headless
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.backend.size()
     }
-}
\ No newline at end of file
+
+    /// Draws the current frame offscreen and reads it back as RGBA pixels, see `WgpuDisplay::read_pixels`. Empty when headless
+    pub fn read_pixels(&mut self) -> Result<Vec<u8>, Chip8Error> {
+        self.backend.set_second_plane(&self.second_plane);
+        self.backend.read_pixels(&self.pixels)
+    }
+}
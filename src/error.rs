@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Everything that can go wrong while loading or running a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The program doesn't fit in the memory after 0x200
+    RomTooLarge,
+    /// 2NNN was executed with all 16 stack slots in use
+    StackOverflow,
+    /// 00EE was executed outside of a subroutine
+    StackUnderflow,
+    /// An instruction accessed memory past the end of RAM, holds the first address accessed that doesn't exist
+    OutOfBoundsMemory(usize),
+    /// An opcode that isn't a supported instruction, see `instruction::supported_opcodes`
+    UnknownOpcode(u16),
+    /// No audio output could be opened, the machine still runs but silently
+    AudioInit(String),
+    /// No GPU could be set up to draw to the window, holds what went wrong including the adapter when one was found
+    GpuInit(String),
+    /// The software renderer couldn't draw to the window
+    SoftwareRendererInit(String),
+    /// Game controllers aren't supported on this platform, the keyboard still works
+    GamepadInit(String),
+    /// Presenting a frame failed, see `Chip8::tick_once`
+    Render(wgpu::SurfaceError),
+    /// A frame couldn't be read back from the renderer, see `Chip8::capture_frame`
+    FrameCapture(String),
+    /// Packed pixels of this many bytes don't match any resolution, see `Display::set_packed_bits`
+    PackedDisplaySize(usize),
+    /// Writing a GIF recording failed, see `Chip8::start_gif_recording`
+    GifRecording(String),
+    /// A debugging command ran this many cycles without getting where it was going, see `Chip8::step_out`
+    CycleLimitReached(u32),
+    /// The font doesn't fit between this `Chip8Config::font_base` and the program or the end of memory
+    FontOutOfRange(usize),
+    /// `Chip8Config::ram_size` is this many bytes, which leaves no room for a program after 0x200
+    RamTooSmall(usize)
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RomTooLarge => write!(f, "the ROM doesn't fit in the memory after 0x200"),
+            Self::StackOverflow => write!(f, "called a subroutine with the stack full"),
+            Self::StackUnderflow => write!(f, "returned without being in a subroutine"),
+            Self::OutOfBoundsMemory(address) => write!(f, "accessed {address:04X}, past the end of memory"),
+            Self::UnknownOpcode(opcode) => write!(f, "unknown opcode {opcode:04X}"),
+            Self::AudioInit(message) => write!(f, "couldn't initialize audio: {message}"),
+            Self::GpuInit(message) => write!(f, "couldn't initialize the GPU: {message}"),
+            Self::SoftwareRendererInit(message) => write!(f, "couldn't initialize the software renderer: {message}"),
+            Self::GamepadInit(message) => write!(f, "couldn't initialize gamepad support: {message}"),
+            Self::Render(error) => write!(f, "couldn't render: {error}"),
+            Self::FrameCapture(message) => write!(f, "couldn't capture the frame: {message}"),
+            Self::PackedDisplaySize(len) => write!(f, "{len} bytes of packed pixels don't match any resolution"),
+            Self::GifRecording(message) => write!(f, "couldn't record the GIF: {message}"),
+            Self::CycleLimitReached(cycles) => write!(f, "gave up after {cycles} cycles"),
+            Self::FontOutOfRange(base) => write!(f, "the font at {base:04X} doesn't fit below the program"),
+            Self::RamTooSmall(size) => write!(f, "{size} bytes of memory leave no room for a program after 0x200")
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
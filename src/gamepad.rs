@@ -0,0 +1,71 @@
+//! Game controller input through gilrs. Kept apart from `Chip8` so the core doesn't depend on gilrs: the host polls
+//! `Gamepads` every frame and the buttons press keypad keys with `Chip8::press_key`, the same way the keyboard does
+
+use std::collections::{HashMap, HashSet};
+use gilrs::{Button, EventType, GamepadId, Gilrs};
+use crate::{chip8::Chip8, error::Chip8Error};
+
+/// Every connected controller, mapped to the keypad by button
+pub struct Gamepads {
+    gilrs: Gilrs,
+    button_map: HashMap<Button, u8>,
+    held: HashMap<GamepadId, HashSet<u8>> // The keys each controller is holding down, released if it disconnects
+}
+
+impl Gamepads {
+    /// Starts listening for controllers with the default button map. Fails on platforms gilrs doesn't support
+    pub fn new() -> Result<Self, Chip8Error> {
+        Self::with_button_map(Self::default_button_map())
+    }
+
+    pub fn with_button_map(button_map: HashMap<Button, u8>) -> Result<Self, Chip8Error> {
+        let gilrs = Gilrs::new().map_err(|error| Chip8Error::GamepadInit(error.to_string()))?;
+
+        Ok(Self { gilrs, button_map, held: HashMap::new() })
+    }
+
+    /// Presses the same keys as the keyboard's default map would: the d-pad is WASD (5, 7, 8, 9), the face buttons
+    /// Q (4), E (6), R (D) and F (E), select X (0) and start V (F)
+    pub fn default_button_map() -> HashMap<Button, u8> {
+        HashMap::from([
+            (Button::DPadUp, 0x5),
+            (Button::DPadLeft, 0x7),
+            (Button::DPadDown, 0x8),
+            (Button::DPadRight, 0x9),
+            (Button::South, 0x6),
+            (Button::East, 0x4),
+            (Button::West, 0xD),
+            (Button::North, 0xE),
+            (Button::Select, 0x0),
+            (Button::Start, 0xF)
+        ])
+    }
+
+    /// Feeds the button presses and releases since the last poll to the machine. Call it once per frame
+    pub fn poll(&mut self, chip8: &mut Chip8) {
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => if let Some(&key) = self.button_map.get(&button) {
+                    self.held.entry(event.id).or_default().insert(key);
+                    chip8.press_key(key);
+                },
+                EventType::ButtonReleased(button, _) => if let Some(&key) = self.button_map.get(&button) {
+                    self.held.entry(event.id).or_default().remove(&key);
+                    chip8.release_key(key);
+                },
+                EventType::Connected => {
+                    info!("Connected gamepad {}", self.gilrs.gamepad(event.id).name());
+                },
+                EventType::Disconnected => {
+                    info!("Disconnected gamepad {}", self.gilrs.gamepad(event.id).name());
+
+                    // The releases won't arrive anymore
+                    for key in self.held.remove(&event.id).unwrap_or_default() {
+                        chip8.release_key(key);
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+}
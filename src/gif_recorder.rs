@@ -0,0 +1,116 @@
+//! Recording the display into an animated GIF, see `Chip8::start_gif_recording`. The screen is captured on every timer
+//! tick and a frame is only written when it changes, the unchanged ticks just make the previous frame last longer
+
+use std::{borrow::Cow, fs::File, io::BufWriter, path::Path};
+use gif::{Encoder, Frame, Repeat};
+use crate::{display::{Display, Palette}, error::Chip8Error};
+
+/// How many GIF pixels a hires pixel takes on each side, lowres pixels take twice as many
+const SCALE: usize = 4;
+const WIDTH: usize = Display::MAX_WIDTH * SCALE;
+const HEIGHT: usize = Display::MAX_HEIGHT * SCALE;
+
+/// GIF delays are in hundredths of a second and most viewers play anything shorter than 2 as if it was much slower,
+/// so frames are never shorter even if the screen changed sooner
+const MIN_DELAY: u64 = 2;
+
+pub struct GifRecorder {
+    encoder: Encoder<BufWriter<File>>,
+    palette: Palette, // The global palette, frames drawn with another one carry their own
+    pending: Option<(Vec<u8>, Palette)>, // The last frame captured, written once it's known how long it lasts
+    ticks: u64, // Timer ticks since the recording started
+    written: u64, // How long the frames written so far last, in hundredths of a second
+    error: Option<String> // The first write that failed, nothing else is written after it
+}
+
+impl GifRecorder {
+    /// Creates the file and captures the screen as the first frame
+    pub fn new(path: &Path, display: &Display) -> Result<Self, Chip8Error> {
+        let file = File::create(path).map_err(|error| Chip8Error::GifRecording(error.to_string()))?;
+        let palette = display.palette();
+
+        let mut encoder = Encoder::new(BufWriter::new(file), WIDTH as u16, HEIGHT as u16, &palette.concat())
+            .map_err(|error| Chip8Error::GifRecording(error.to_string()))?;
+        encoder.set_repeat(Repeat::Infinite).map_err(|error| Chip8Error::GifRecording(error.to_string()))?;
+
+        let mut recorder = Self { encoder, palette, pending: None, ticks: 0, written: 0, error: None };
+        recorder.capture(display);
+
+        Ok(recorder)
+    }
+
+    /// Called on every timer tick
+    pub fn tick(&mut self, display: &Display) {
+        self.ticks += 1;
+        self.capture(display);
+    }
+
+    fn capture(&mut self, display: &Display) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let pixels = Self::indexed_pixels(display);
+        let palette = display.palette();
+
+        if self.pending.as_ref().is_some_and(|(pending, pending_palette)| *pending == pixels && *pending_palette == palette) {
+            return;
+        }
+
+        if let Some(previous) = self.pending.replace((pixels, palette)) {
+            self.write(previous);
+        }
+    }
+
+    /// Writes the frame that lasted until the current tick
+    fn write(&mut self, (pixels, palette): (Vec<u8>, Palette)) {
+        // Measured from the start of the recording so the rounding doesn't add up over many frames
+        let end = self.ticks * 100 / crate::chip8::FRAMES_PER_SECOND as u64;
+        let delay = end.saturating_sub(self.written).max(MIN_DELAY);
+        self.written += delay;
+
+        let frame = Frame {
+            width: WIDTH as u16,
+            height: HEIGHT as u16,
+            delay: delay.min(u16::MAX as u64) as u16,
+            palette: (palette != self.palette).then(|| palette.concat()),
+            buffer: Cow::Owned(pixels),
+            ..Frame::default()
+        };
+
+        if let Err(error) = self.encoder.write_frame(&frame) {
+            warn!("Stopped recording the GIF: {error}");
+            self.error = Some(error.to_string());
+        }
+    }
+
+    /// The palette index of every pixel, scaled up to the GIF's size
+    fn indexed_pixels(display: &Display) -> Vec<u8> {
+        let scale_x = WIDTH / display.width();
+        let scale_y = HEIGHT / display.height();
+
+        let mut pixels = Vec::with_capacity(WIDTH * HEIGHT);
+
+        for y in 0..HEIGHT {
+            pixels.extend((0..WIDTH).map(|x| display.color_index(x / scale_x, y / scale_y) as u8));
+        }
+
+        pixels
+    }
+
+    /// Writes the last frame and the end of the GIF. Fails if any write did
+    pub fn finish(mut self) -> Result<(), Chip8Error> {
+        if let Some(last) = self.pending.take() {
+            self.write(last);
+        }
+
+        if let Some(error) = self.error {
+            return Err(Chip8Error::GifRecording(error));
+        }
+
+        self.encoder.into_inner()
+            .and_then(|writer| writer.into_inner().map_err(|error| error.into_error()))
+            .map(drop)
+            .map_err(|error| Chip8Error::GifRecording(error.to_string()))
+    }
+}
@@ -0,0 +1,168 @@
+/// A decoded 16 bit CHIP-8 instruction split into its four nibbles
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Instruction {
+    digit1: u16,
+    digit2: u16,
+    digit3: u16,
+    digit4: u16
+}
+
+impl Instruction {
+    /// Decodes an instruction from its big endian 16 bit value
+    pub fn new(instruction: u16) -> Self {
+        Self {
+            digit1: (instruction & 0xF000) >> 12,
+            digit2: (instruction & 0x0F00) >> 8,
+            digit3: (instruction & 0x00F0) >> 4,
+            digit4: instruction & 0xF
+        }
+    }
+
+    /// Decodes an instruction from the two bytes it's stored as in memory
+    pub fn from_bytes(high: u8, low: u8) -> Self {
+        Self::new(u16::from_be_bytes([high, low]))
+    }
+
+    /// Re-encodes the instruction into its 16 bit value
+    pub fn opcode(&self) -> u16 {
+        self.digit1 << 12 | self.digit2 << 8 | self.digit3 << 4 | self.digit4
+    }
+
+    /// Returns the first byte of the instruction (ltr)
+    pub fn d1(&self) -> u16 {
+        self.digit1
+    }
+
+    /// Returns the second byte of the instruction (ltr)
+    pub fn d2(&self) -> u16 {
+        self.digit2
+    }
+
+    /// Returns the third byte of the instruction (ltr)
+    pub fn d3(&self) -> u16 {
+        self.digit3
+    }
+
+    /// Returns the fourth byte of the instruction (ltr)
+    pub fn d4(&self) -> u16 {
+        self.digit4
+    }
+    
+    /// Returns the X and Y register indices (the second and third nibbles) for instructions of the form `_XY_`
+    pub fn xy(&self) -> (usize, usize) {
+        (self.x(), self.y())
+    }
+
+    /// Returns the X register index, the second nibble of the instruction
+    pub fn x(&self) -> usize {
+        self.digit2 as usize
+    }
+
+    /// Returns the Y register index, the third nibble of the instruction
+    pub fn y(&self) -> usize {
+        self.digit3 as usize
+    }
+
+    /// Returns the fourth nibble of the instruction
+    pub fn n(&self) -> u16 {
+        self.digit4
+    }
+
+    /// Returns the third and fourth bytes of the instruction (ltr)
+    pub fn nn(&self) -> u16 {
+        self.digit3 << 4 | self.digit4
+    }
+
+    /// Returns the second, third, and fourth byte of the instruction (ltr)
+    pub fn nnn(&self) -> u16 {
+        self.digit2 << 8 | self.digit3 << 4 | self.digit4
+    }
+}
+
+/// Which instruction set an opcode belongs to
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Extension {
+    /// The original CHIP-8 instructions
+    Core,
+    SuperChip,
+    XoChip
+}
+
+/// Describes an opcode the interpreter executes, for documentation and debugger UIs
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct OpcodeInfo {
+    /// The opcode with its operands written as the letters they stand for, e.g. `8XY4`
+    pub pattern: &'static str,
+    pub description: &'static str,
+    pub extension: Extension
+}
+
+impl OpcodeInfo {
+    const fn core(pattern: &'static str, description: &'static str) -> Self {
+        Self { pattern, description, extension: Extension::Core }
+    }
+
+    const fn super_chip(pattern: &'static str, description: &'static str) -> Self {
+        Self { pattern, description, extension: Extension::SuperChip }
+    }
+
+    /// Whether an opcode is an instance of this pattern, with `X`, `Y`, `N` matching any nibble
+    pub fn matches(&self, opcode: u16) -> bool {
+        self.pattern.chars().enumerate().all(|(i, digit)| {
+            let nibble = (opcode >> (12 - i * 4)) & 0xF;
+
+            match digit.to_digit(16) {
+                Some(value) => nibble == value as u16,
+                None => true
+            }
+        })
+    }
+}
+
+// Keep in sync with the match in `Chip8::excecute_instruction`
+const OPCODES: &[OpcodeInfo] = &[
+    OpcodeInfo::core("00E0", "Clear the screen"),
+    OpcodeInfo::core("00EE", "Return from a subroutine"),
+    OpcodeInfo::super_chip("00CN", "Scroll the screen down N rows, N/2 in lowres with the lowres_scroll_halved quirk"),
+    OpcodeInfo::super_chip("00FB", "Scroll the screen right 4 columns, 2 in lowres with the lowres_scroll_halved quirk"),
+    OpcodeInfo::super_chip("00FC", "Scroll the screen left 4 columns, 2 in lowres with the lowres_scroll_halved quirk"),
+    OpcodeInfo::super_chip("00FE", "Switch to the 64x32 lowres mode and clear the screen"),
+    OpcodeInfo::super_chip("00FF", "Switch to the 128x64 hires mode and clear the screen"),
+    OpcodeInfo::core("1NNN", "Jump to NNN"),
+    OpcodeInfo::core("2NNN", "Call the subroutine at NNN"),
+    OpcodeInfo::core("3XNN", "Skip the next instruction if VX == NN"),
+    OpcodeInfo::core("4XNN", "Skip the next instruction if VX != NN"),
+    OpcodeInfo::core("5XY0", "Skip the next instruction if VX == VY"),
+    OpcodeInfo::core("6XNN", "Set VX to NN"),
+    OpcodeInfo::core("7XNN", "Add NN to VX without setting the carry flag"),
+    OpcodeInfo::core("8XY0", "Set VX to VY"),
+    OpcodeInfo::core("8XY1", "Set VX to VX | VY"),
+    OpcodeInfo::core("8XY2", "Set VX to VX & VY"),
+    OpcodeInfo::core("8XY3", "Set VX to VX ^ VY"),
+    OpcodeInfo::core("8XY4", "Add VY to VX, VF = 1 on overflow"),
+    OpcodeInfo::core("8XY5", "Set VX to VX - VY, VF = 0 on underflow"),
+    OpcodeInfo::core("8XY6", "Shift VX right by one, VF = the shifted out bit. Shifts VY into VX with the shift_uses_vy quirk"),
+    OpcodeInfo::core("8XY7", "Set VX to VY - VX, VF = 0 on underflow"),
+    OpcodeInfo::core("8XYE", "Shift VX left by one, VF = the shifted out bit. Shifts VY into VX with the shift_uses_vy quirk"),
+    OpcodeInfo::core("9XY0", "Skip the next instruction if VX != VY"),
+    OpcodeInfo::core("ANNN", "Set I to NNN"),
+    OpcodeInfo::core("BNNN", "Jump to NNN + V0, or NNN + VX with the jump_uses_vx quirk"),
+    OpcodeInfo::core("CXNN", "Set VX to a random number & NN"),
+    OpcodeInfo::core("DXYN", "Draw the N byte sprite at I to (VX, VY), VF = 1 if a pixel was erased"),
+    OpcodeInfo::core("EX9E", "Skip the next instruction if the key in VX is pressed"),
+    OpcodeInfo::core("EXA1", "Skip the next instruction if the key in VX isn't pressed"),
+    OpcodeInfo::core("FX07", "Set VX to the delay timer"),
+    OpcodeInfo::core("FX0A", "Wait for a key press and store it in VX"),
+    OpcodeInfo::core("FX15", "Set the delay timer to VX"),
+    OpcodeInfo::core("FX18", "Set the sound timer to VX"),
+    OpcodeInfo::core("FX1E", "Add VX to I"),
+    OpcodeInfo::core("FX29", "Point I to the font sprite of the digit in VX"),
+    OpcodeInfo::core("FX33", "Store the decimal digits of VX at I, I + 1 and I + 2"),
+    OpcodeInfo::core("FX55", "Store V0 to VX in memory starting at I"),
+    OpcodeInfo::core("FX65", "Load V0 to VX from memory starting at I"),
+];
+
+/// Lists every opcode this build executes, anything else is ignored by the interpreter
+pub fn supported_opcodes() -> Vec<OpcodeInfo> {
+    OPCODES.to_vec()
+}
@@ -4,65 +4,135 @@ use winit::event::VirtualKeyCode;
 pub struct Keyboard {
     key_map: HashMap<VirtualKeyCode, u8>,
     keys_down: HashSet<u8>,
+    latched_keys: Option<HashSet<u8>>, // The keys down at the last `latch`, None when keys are read as they are
     pub awaiting_key_press: bool, // Where the chip 8 is waiting for a keypress
     pub recieved_key_press: bool,
     last_key_pressed: u8
 }
 
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Keyboard {
     pub fn new() -> Self {
-        Self { 
-            key_map: HashMap::from([
-                (VirtualKeyCode::Key1, 0x1),
-                (VirtualKeyCode::Key2, 0x2),
-                (VirtualKeyCode::Key3, 0x3),
-                (VirtualKeyCode::Key4, 0xC),
-                (VirtualKeyCode::Q, 0x4),
-                (VirtualKeyCode::W, 0x5),
-                (VirtualKeyCode::E, 0x6),
-                (VirtualKeyCode::R, 0xD),
-                (VirtualKeyCode::A, 0x7),
-                (VirtualKeyCode::S, 0x8),
-                (VirtualKeyCode::D, 0x9),
-                (VirtualKeyCode::F, 0xE),
-                (VirtualKeyCode::Z, 0xA),
-                (VirtualKeyCode::X, 0x0),
-                (VirtualKeyCode::C, 0xB),
-                (VirtualKeyCode::V, 0xF)
-            ]), 
+        Self::with_key_map(Self::default_key_map())
+    }
+
+    pub fn with_key_map(key_map: HashMap<VirtualKeyCode, u8>) -> Self {
+        Self {
+            key_map,
             keys_down: HashSet::new(),
+            latched_keys: None,
             awaiting_key_press: false,
             recieved_key_press: false,
             last_key_pressed: 0
         }
     }
 
+    /// Maps the left side of a QWERTY keyboard (1234/QWER/ASDF/ZXCV) to the CHIP-8's 4x4 keypad
+    pub fn default_key_map() -> HashMap<VirtualKeyCode, u8> {
+        HashMap::from([
+            (VirtualKeyCode::Key1, 0x1),
+            (VirtualKeyCode::Key2, 0x2),
+            (VirtualKeyCode::Key3, 0x3),
+            (VirtualKeyCode::Key4, 0xC),
+            (VirtualKeyCode::Q, 0x4),
+            (VirtualKeyCode::W, 0x5),
+            (VirtualKeyCode::E, 0x6),
+            (VirtualKeyCode::R, 0xD),
+            (VirtualKeyCode::A, 0x7),
+            (VirtualKeyCode::S, 0x8),
+            (VirtualKeyCode::D, 0x9),
+            (VirtualKeyCode::F, 0xE),
+            (VirtualKeyCode::Z, 0xA),
+            (VirtualKeyCode::X, 0x0),
+            (VirtualKeyCode::C, 0xB),
+            (VirtualKeyCode::V, 0xF)
+        ])
+    }
+
     pub fn is_pressed(&self, key: u8) -> bool {
         self.keys_down.contains(&key)
     }
 
+    /// Makes `is_pressed_this_frame` read the keys that were down at the last `latch` instead of the ones down right
+    /// now, so presses and releases only take effect once per frame
+    pub fn set_latching(&mut self, enabled: bool) {
+        self.latched_keys = enabled.then(|| self.keys_down.clone());
+    }
+
+    /// Takes a snapshot of the keys down for `is_pressed_this_frame`, does nothing unless latching
+    pub fn latch(&mut self) {
+        if let Some(latched_keys) = &mut self.latched_keys {
+            latched_keys.clone_from(&self.keys_down);
+        }
+    }
+
+    /// Whether a key is pressed as seen by the program's key checks, see `set_latching`
+    pub fn is_pressed_this_frame(&self, key: u8) -> bool {
+        self.latched_keys.as_ref().unwrap_or(&self.keys_down).contains(&key)
+    }
+
+    /// Whether a key down event is an actual press rather than the OS repeating a key that's held down
+    pub fn is_key_event_new(&self, key: &VirtualKeyCode) -> bool {
+        !self.key_map.get(key).is_some_and(|key_code| self.keys_down.contains(key_code))
+    }
+
+    /// The keypad key a keyboard key is mapped to
+    pub fn key_code(&self, key: &VirtualKeyCode) -> Option<u8> {
+        self.key_map.get(key).copied()
+    }
+
+    /// Presses a key. Repeated presses of a key that's already down are ignored
     pub fn on_key_down(&mut self, key: &VirtualKeyCode) {
-        let key_code = self.key_map.get(key);
+        if !self.is_key_event_new(key) {
+            return;
+        }
 
-        if key_code.is_some() {
-            self.keys_down.insert(*key_code.unwrap());
+        if let Some(key_code) = self.key_code(key) {
+            self.press(key_code);
         }
     }
 
     pub fn on_key_up(&mut self, key: &VirtualKeyCode) {
-        let key_code = self.key_map.get(key);
+        if let Some(key_code) = self.key_code(key) {
+            self.release(key_code);
+        }
+    }
 
-        if key_code.is_some() {
-            self.keys_down.remove(key_code.unwrap());
+    /// Presses a key on the keypad directly, without going through the key map
+    pub fn press(&mut self, key_code: u8) {
+        self.keys_down.insert(key_code);
+    }
+
+    /// Releases a key on the keypad, which completes an `FX0A` waiting for a key press
+    pub fn release(&mut self, key_code: u8) {
+        self.keys_down.remove(&key_code);
 
-            
-            if self.awaiting_key_press {
-                self.awaiting_key_press = false;
-                self.recieved_key_press = true;
-                self.last_key_pressed = *key_code.unwrap();
-            }
+        if self.awaiting_key_press {
+            self.awaiting_key_press = false;
+            self.recieved_key_press = true;
+            self.last_key_pressed = key_code;
         }
     }
 
+    /// Releases every key without counting it as a key press, for when the key ups won't arrive (e.g. the window lost
+    /// focus). Unlike `reset` an `FX0A` in progress keeps waiting
+    pub fn clear(&mut self) {
+        self.keys_down.clear();
+    }
+
+    /// Releases every key and stops waiting for a key press, keeping the key map
+    pub fn reset(&mut self) {
+        self.keys_down.clear();
+        self.latch();
+        self.awaiting_key_press = false;
+        self.recieved_key_press = false;
+        self.last_key_pressed = 0;
+    }
+
     pub fn get_last_key_pressed(&self) -> u8 {self.last_key_pressed}
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+#[macro_use]
+mod logging;
+
+pub mod asm;
+#[cfg(feature = "audio")]
+mod audio;
+mod auto_speed;
+pub mod chip8;
+pub mod compat;
+pub mod config;
+pub mod display;
+pub mod error;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+#[cfg(feature = "gif")]
+mod gif_recorder;
+pub mod instruction;
+pub mod keyboard;
+#[cfg(feature = "net")]
+pub mod net;
+mod overlay;
+pub mod profiles;
+pub mod quirks;
+#[cfg(feature = "persist")]
+pub mod recent;
+pub mod renderer;
+pub mod replay;
+#[cfg(feature = "persist")]
+pub mod settings;
+#[cfg(feature = "software")]
+pub mod software;
+
+#[cfg(feature = "audio")]
+pub use audio::AudioMixer;
+pub use crate::chip8::Chip8;
+pub use config::Chip8Config;
+pub use error::Chip8Error;
+pub use quirks::{QuirkPreset, Quirks};
+pub use instruction::Instruction;
@@ -0,0 +1,48 @@
+//! Messages go through the `log` crate with the log feature so embedders can route them to any logger (e.g. env_logger).
+//! Without it every message is dropped, a library shouldn't write to the host's stdout or stderr. The arguments are
+//! still passed to `format_args!` so they're type checked and don't leave unused variable warnings behind
+
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) }
+}
+
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } }
+}
+
+#[cfg(feature = "log")]
+macro_rules! info {
+    ($($arg:tt)*) => { log::info!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! info {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } }
+}
+
+#[cfg(feature = "log")]
+macro_rules! warn {
+    ($($arg:tt)*) => { log::warn!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } }
+}
+
+#[cfg(feature = "log")]
+macro_rules! error {
+    ($($arg:tt)*) => { log::error!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! error {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } }
+}
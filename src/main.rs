@@ -1,48 +1,198 @@
-mod chip8;
-mod display;
-mod keyboard;
-use winit::event::{Event, WindowEvent, KeyboardInput, VirtualKeyCode};
+use chip8::{Chip8, Chip8Config, Chip8Error, chip8::{FRAMES_PER_SECOND, MAX_PROGRAM_SIZE}, display, renderer};
+use winit::event::{Event, WindowEvent, KeyboardInput, VirtualKeyCode, ElementState};
 
 use rfd::AsyncFileDialog;
+#[cfg(feature = "software")]
+use chip8::software::SoftwareRenderer;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+const WINDOW_TITLE: &str = "Chip 8";
+
+/// How long each 60Hz frame lasts
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND as u64);
+
+/// How often the delay and sound timers are decremented, once per frame
+const TIMER_INTERVAL: Duration = FRAME_DURATION;
 
 fn main() {
-    let rom = pollster::block_on(AsyncFileDialog::new().set_directory("./").add_filter("chip8", &["ch8"]).pick_file());
+    // Warnings are shown by default, without the log feature the library's messages are dropped and only what the
+    // binary prints itself is shown
+    #[cfg(feature = "log")]
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let vsync = !std::env::args().any(|arg| arg == "--no-vsync");
+
+    let config = Chip8Config {
+        gpu: renderer::GpuConfig {
+            power_preference: if std::env::args().any(|arg| arg == "--high-performance") {
+                wgpu::PowerPreference::HighPerformance
+            } else {
+                wgpu::PowerPreference::LowPower
+            },
+            force_fallback_adapter: std::env::args().any(|arg| arg == "--fallback-adapter")
+        },
+        halt_on_error: true,
+        crash_screen: true,
+        start_paused: std::env::args().any(|arg| arg == "--paused"),
+        ..Default::default()
+    };
+
+    // Each CHIP-8 pixel is shown as a scale x scale square
+    let scale: u32 = arg_value("--scale")
+        .map(|value| value.parse().expect("--scale expects a positive number"))
+        .unwrap_or(16);
 
-    let rom_data = pollster::block_on(rom.unwrap().read());
+    // While the turbo key is held this many times more cycles run per frame and timers tick this much faster
+    let turbo_multiplier: u32 = arg_value("--turbo")
+        .map(|value| value.parse().expect("--turbo expects a positive number"))
+        .unwrap_or(8);
+
+    // `-` reads the ROM from stdin instead of asking for one, e.g. `cat game.ch8 | chip8 -`
+    let stdin_rom = if std::env::args().skip(1).any(|arg| arg == "-") {
+        match read_stdin_rom() {
+            Ok(rom) => Some(rom),
+            Err(error) => {
+                // Scripts piping a ROM in expect a failure to end the program rather than open an empty window
+                eprintln!("Couldn't load the ROM from stdin: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let rom = if stdin_rom.is_none() {pick_rom()} else {None};
 
     let event_loop = winit::event_loop::EventLoop::new();
 
     let window = winit::window::WindowBuilder::new()
-    .with_title("Chip 8")
-    .with_inner_size(winit::dpi::Size::Physical(winit::dpi::PhysicalSize::new(1080, 540)))
+    .with_title(WINDOW_TITLE)
+    .with_inner_size(winit::dpi::Size::Physical(display::Display::window_size(scale)))
     .build(&event_loop)
     .unwrap();
 
-    let mut chip8 = chip8::Chip8::new(&window);
+    let mut chip8 = match create_chip8(config, &window) {
+        Ok(chip8) => chip8,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!("Try --fallback-adapter to render without a GPU, or updating the graphics drivers");
+            std::process::exit(1);
+        }
+    };
 
-    chip8.load_program(rom_data);
+    #[cfg(feature = "audio")]
+    if chip8.audio_mixer().is_none() {
+        eprintln!("No audio output could be opened, sound is disabled");
+    }
 
-    let instructions_per_frame: u64 = 60;
-    
-    let sound_interval = std::time::Instant::now();
+    #[cfg(feature = "persist")]
+    match chip8::profiles::load_user_profiles() {
+        Ok(profiles) => chip8.add_known_profiles(profiles),
+        Err(error) => eprintln!("{error}, ignoring the user profiles")
+    }
 
-    event_loop.run(move |event, _, control_flow| {
-        if sound_interval.elapsed() >= std::time::Duration::from_secs_f32(0.016) {
-            chip8.tick_timers();
+    // Added last so they take priority over the ones in the config directory
+    if let Some(path) = arg_value("--profiles") {
+        match chip8::profiles::from_file(std::path::Path::new(&path)) {
+            Ok(profiles) => chip8.add_known_profiles(profiles),
+            Err(error) => eprintln!("{error}, ignoring --profiles")
+        }
+    }
+
+    if let Some(rom_data) = stdin_rom {
+        chip8.load_program(rom_data).expect("the ROM's size is checked when it's read");
+        chip8.set_rom_name("stdin");
+        apply_known_profile(&mut chip8);
+    } else {
+        match rom.map(|rom| (read_rom(rom.path()), rom)) {
+            Some((Ok(rom_data), rom)) => {
+                chip8.load_program(rom_data).expect("the ROM's size is checked when it's read");
+                chip8.set_rom_name(rom.file_name());
+                apply_known_profile(&mut chip8);
+
+                #[cfg(feature = "persist")]
+                remember_rom(&mut chip8, rom.path());
+            },
+            result => {
+                if let Some((Err(error), rom)) = result {
+                    eprintln!("Couldn't load {}: {error}", rom.path().display());
+                }
+
+                // Jumping to itself keeps the machine idle until a ROM is opened with O or dropped onto the window
+                println!("No ROM loaded, press O or drop a .ch8 file onto the window to open one");
+                chip8.load_program(vec![0x12, 0x00]).expect("a single instruction fits in memory");
+            }
         }
+    }
+
+    if !vsync {
+        chip8.set_vsync(false);
+    }
+
+    chip8.set_scanlines(std::env::args().any(|arg| arg == "--scanlines"));
+    chip8.set_grid(std::env::args().any(|arg| arg == "--grid"));
+    chip8.set_color_cycle(std::env::args().any(|arg| arg == "--color-cycle"), 0.25);
+    chip8.enable_auto_speed(std::env::args().any(|arg| arg == "--auto-speed"));
+    chip8.set_max_draws_per_frame(arg_value("--max-draws").map(|value| value.parse().expect("--max-draws expects a positive number")));
+
+    window.set_title(&window_title(&chip8, None));
+
+    let mut turbo = false;
+
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = chip8::gamepad::Gamepads::new()
+        .map_err(|error| eprintln!("{error}, only the keyboard can be used"))
+        .ok();
+
+    #[cfg(feature = "net")]
+    let mut spectators = arg_value("--spectate").and_then(|address| {
+        chip8::net::SpectatorServer::bind(&address)
+            .map_err(|error| eprintln!("Couldn't stream to spectators on {address}: {error}"))
+            .ok()
+    });
+
+    #[cfg(feature = "net")]
+    let mut remote_players = arg_value("--remote-input").and_then(|address| {
+        chip8::net::InputServer::bind(&address)
+            .map_err(|error| eprintln!("Couldn't take remote input on {address}: {error}"))
+            .ok()
+    });
+
+    // Hovering a file shows what dropping it would load, the speed readout waits until it's dropped or cancelled
+    let mut hovering_file = false;
+    let mut speed_meter = SpeedMeter::new(&chip8);
+
+    // Frames are paced by sleeping until the next one is due instead of running whenever winit wakes up,
+    // which keeps the emulation speed the same regardless of how many events arrive
+    let mut next_frame = Instant::now();
+
+    // Timers keep their own schedule so they count down at 60Hz even when the window receives no events
+    let mut next_timer_tick = Instant::now() + TIMER_INTERVAL;
 
-        control_flow.set_wait_timeout(std::time::Duration::from_millis(1000 / instructions_per_frame));
-        
+    event_loop.run(move |event, _, control_flow| {
         match event {
             Event::WindowEvent {
                 event,
                 ..
             } => {
                 match event {
-                    WindowEvent::CloseRequested => {control_flow.set_exit();},
+                    WindowEvent::CloseRequested => {
+                        #[cfg(feature = "persist")]
+                        save_settings(&chip8);
+
+                        // Otherwise the GIF would be left without its last frame
+                        #[cfg(feature = "gif")]
+                        if let Err(error) = chip8.stop_gif_recording() {
+                            eprintln!("{error}");
+                        }
+
+                        control_flow.set_exit();
+                    },
                     WindowEvent::KeyboardInput { 
                         input: KeyboardInput {
                             virtual_keycode: Some(VirtualKeyCode::P),
+                            state: ElementState::Pressed,
                             ..
                         },
                         .. 
@@ -50,36 +200,476 @@ fn main() {
                         chip8.paused = !chip8.paused;
                     },
                     WindowEvent::KeyboardInput { 
-                        input,
-                        ..
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::N),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } if chip8.paused => {
+                        report_pause_on_error(chip8.step());
+                    },
+                    // M toggles mute, so frame stepping sits next to N on B
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::B),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } if chip8.paused => {
+                        report_pause_on_error(chip8.step_frame());
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::U),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } if chip8.paused => {
+                        match chip8.step_out() {
+                            Err(error @ Chip8Error::CycleLimitReached(_)) => eprintln!("Stepping out of the subroutine {error}"),
+                            result => report_pause_on_error(result)
+                        }
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::F1),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        let vsync = !chip8.is_vsync();
+                        if let Some(present_mode) = chip8.set_vsync(vsync) {
+                            println!("Present mode: {present_mode:?}");
+                        }
+                    },
+                    #[cfg(feature = "audio")]
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::M),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        let muted = !chip8.is_muted();
+                        chip8.set_muted(muted);
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::F3),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        let debug_overlay = !chip8.is_debug_overlay();
+                        chip8.set_debug_overlay(debug_overlay);
+                    },
+                    #[cfg(feature = "debug")]
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::F4),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        let ghost_pixels = !chip8.is_ghost_pixels();
+                        chip8.set_ghost_pixels(ghost_pixels);
+                    },
+                    #[cfg(feature = "gif")]
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::F5),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        toggle_gif_recording(&mut chip8);
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::O),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        .. 
                     } => {
-                        if input.virtual_keycode.is_some() {
-                            match input.state {
-                                winit::event::ElementState::Pressed => {
-                                    chip8.on_key_down(&input.virtual_keycode.unwrap());
+                        let was_paused = chip8.paused;
+                        chip8.paused = true;
+
+                        // Cancelling the dialog keeps the current ROM running
+                        if let Some(rom) = pick_rom() {
+                            match read_rom(rom.path()) {
+                                Ok(rom_data) => {
+                                    switch_rom(&mut chip8, rom.path(), rom_data);
+                                    speed_meter = SpeedMeter::new(&chip8);
+                                    window.set_title(&window_title(&chip8, None));
                                 },
-                                winit::event::ElementState::Released => {
-                                    chip8.on_key_up(&input.virtual_keycode.unwrap());
+                                Err(error) => {
+                                    eprintln!("Couldn't load {}: {error}", rom.path().display());
+                                    chip8.paused = was_paused;
                                 }
                             }
+                        } else {
+                            chip8.paused = was_paused;
+                        }
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::Space),
+                            state,
+                            ..
+                        },
+                        .. 
+                    } => {
+                        turbo = state == ElementState::Pressed;
+                    },
+                    WindowEvent::KeyboardInput { 
+                        input: KeyboardInput {
+                            virtual_keycode: Some(keycode),
+                            state,
+                            ..
+                        },
+                        ..
+                    } => {
+                        match state {
+                            ElementState::Pressed => {
+                                chip8.on_key_down(&keycode);
+                            },
+                            ElementState::Released => {
+                                chip8.on_key_up(&keycode);
+                            }
                         }
                     },
+                    // Keys held while switching windows never get released otherwise, so the game would think they're still down
+                    WindowEvent::Focused(false) => {
+                        chip8.clear_keys();
+                        turbo = false;
+                    },
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                         chip8.handle_resize(new_inner_size);
                     },
                     WindowEvent::Resized(new_inner_size) => {
                         chip8.handle_resize(&new_inner_size);
+                    },
+                    WindowEvent::HoveredFile(path) => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        window.set_title(&format!("{WINDOW_TITLE} - Drop to load {name}"));
+                        hovering_file = true;
+                    },
+                    WindowEvent::HoveredFileCancelled => {
+                        hovering_file = false;
+                        window.set_title(&window_title(&chip8, speed_meter.readout()));
+                    },
+                    WindowEvent::DroppedFile(path) => {
+                        hovering_file = false;
+
+                        match read_rom(&path) {
+                            Ok(rom) => {
+                                switch_rom(&mut chip8, &path, rom);
+                                speed_meter = SpeedMeter::new(&chip8);
+                            },
+                            Err(error) => eprintln!("Couldn't load {}: {error}", path.display())
+                        }
+
+                        window.set_title(&window_title(&chip8, speed_meter.readout()));
                     }
                     _ => {}
                 }
 
             },
             Event::MainEventsCleared => {
-                for _ in 0..instructions_per_frame {
-                    chip8.cycle();
+                let now = Instant::now();
+
+                let speed = if turbo {turbo_multiplier} else {1};
+
+                if now >= next_frame {
+                    #[cfg(feature = "gamepad")]
+                    if let Some(gamepads) = &mut gamepads {
+                        gamepads.poll(&mut chip8);
+                    }
+
+                    #[cfg(feature = "net")]
+                    if let Some(remote_players) = &mut remote_players {
+                        remote_players.poll(&mut chip8);
+                    }
+
+                    let result = chip8.run_cycles(chip8.cycles_per_frame() * speed);
+                    speed_meter.count_instructions(*result.as_ref().unwrap_or(&0));
+                    report_pause_on_error(result.map(|_| ()));
+
+                    #[cfg(feature = "net")]
+                    if let Some(spectators) = &mut spectators {
+                        spectators.broadcast(&chip8);
+                    }
+
+                    next_frame += FRAME_DURATION;
+
+                    // Don't try to catch up on frames missed while the loop was blocked (e.g. the window being dragged)
+                    if next_frame < now {
+                        next_frame = now + FRAME_DURATION;
+                    }
+                }
+
+                if now >= next_timer_tick {
+                    for _ in 0..speed {
+                        chip8.tick_timers();
+                    }
+
+                    next_timer_tick += TIMER_INTERVAL;
+
+                    if next_timer_tick < now {
+                        next_timer_tick = now + TIMER_INTERVAL;
+                    }
+                }
+
+                if speed_meter.update(&chip8, now) && !hovering_file {
+                    window.set_title(&window_title(&chip8, speed_meter.readout()));
+                }
+
+                // Presenting only happens in RedrawRequested, some platforms (e.g. macOS) don't show frames drawn elsewhere
+                if chip8.needs_redraw() {
+                    window.request_redraw();
+                }
+
+                control_flow.set_wait_until(next_frame.min(next_timer_tick));
+            },
+            Event::RedrawRequested(_) => {
+                if let Err(error) = chip8.render() {
+                    eprintln!("Failed to render: {error}");
+                    control_flow.set_exit();
                 }
             },
             _ => (),
         }
     });
 }
+
+/// Asks the user for a ROM, None if the dialog was cancelled
+fn pick_rom() -> Option<rfd::FileHandle> {
+    let dialog = AsyncFileDialog::new().set_directory("./").add_filter("chip8", &["ch8"]);
+
+    // Start with the most recently loaded ROM selected
+    #[cfg(feature = "persist")]
+    let dialog = match chip8::recent::recent_roms().first() {
+        Some(recent) => {
+            let dialog = match recent.parent() {
+                Some(directory) => dialog.set_directory(directory),
+                None => dialog
+            };
+
+            match recent.file_name() {
+                Some(file_name) => dialog.set_file_name(&file_name.to_string_lossy()),
+                None => dialog
+            }
+        },
+        None => dialog
+    };
+
+    pollster::block_on(dialog.pick_file())
+}
+
+/// Replaces the running game with another one, keeping the settings of the one that was running
+fn switch_rom(chip8: &mut Chip8, path: &std::path::Path, rom: Vec<u8>) {
+    #[cfg(feature = "persist")]
+    save_settings(chip8);
+
+    chip8.reset_and_reload(rom).expect("the ROM's size is checked when it's read");
+    chip8.set_rom_name(path.file_name().unwrap_or_default().to_string_lossy());
+    apply_known_profile(chip8);
+
+    #[cfg(feature = "persist")]
+    remember_rom(chip8, path);
+}
+
+/// Measures how many 60Hz frames and instructions ran over the last second
+struct SpeedMeter {
+    since: Instant,
+    start_frame: u64,
+    instructions: u64,
+    readout: Option<(u64, u64)> // Frames and instructions per second, None until a second has passed
+}
+
+impl SpeedMeter {
+    fn new(chip8: &Chip8) -> Self {
+        SpeedMeter {
+            since: Instant::now(),
+            start_frame: chip8.frame_count(),
+            instructions: 0,
+            readout: None
+        }
+    }
+
+    fn count_instructions(&mut self, instructions: u32) {
+        self.instructions += instructions as u64;
+    }
+
+    /// Takes a new readout once a second, returning whether it did
+    fn update(&mut self, chip8: &Chip8, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.since).as_secs_f64();
+
+        if elapsed < 1.0 {
+            return false;
+        }
+
+        let frames = chip8.frame_count().saturating_sub(self.start_frame);
+        self.readout = Some(((frames as f64 / elapsed).round() as u64, (self.instructions as f64 / elapsed).round() as u64));
+
+        self.since = now;
+        self.start_frame = chip8.frame_count();
+        self.instructions = 0;
+
+        true
+    }
+
+    fn readout(&self) -> Option<(u64, u64)> {
+        self.readout
+    }
+}
+
+/// The loaded ROM and how fast it's running, e.g. "Chip 8 - pong.ch8 - 60 FPS, 600 IPS"
+fn window_title(chip8: &Chip8, speed: Option<(u64, u64)>) -> String {
+    let Some(name) = chip8.rom_name() else {
+        return format!("{WINDOW_TITLE} - No ROM loaded");
+    };
+
+    if let Some(error) = chip8.crash_error() {
+        return format!("{WINDOW_TITLE} - {name} - Crashed: {error}");
+    }
+
+    match speed {
+        Some((fps, ips)) => format!("{WINDOW_TITLE} - {name} - {fps} FPS, {ips} IPS"),
+        None => format!("{WINDOW_TITLE} - {name}")
+    }
+}
+
+/// The machine pauses itself on a failing instruction (`halt_on_error`) so the state it left can be inspected.
+/// The library only logs the error with the log feature so it's reported here as well
+fn report_pause_on_error(result: Result<(), Chip8Error>) {
+    if let Err(error) = result {
+        eprintln!("{error}");
+        println!("Paused after an error, press P to resume");
+    }
+}
+
+/// Records into a GIF in the working directory named after when the recording started
+#[cfg(feature = "gif")]
+fn toggle_gif_recording(chip8: &mut Chip8) {
+    if chip8.is_recording_gif() {
+        match chip8.stop_gif_recording() {
+            Ok(()) => println!("Stopped recording the GIF"),
+            Err(error) => eprintln!("{error}")
+        }
+
+        return;
+    }
+
+    let started = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = format!("chip8-{started}.gif");
+
+    match chip8.start_gif_recording(&path) {
+        Ok(()) => println!("Recording to {path}, press F5 again to stop"),
+        Err(error) => eprintln!("{error}")
+    }
+}
+
+fn apply_known_profile(chip8: &mut Chip8) {
+    if let Some(name) = chip8.apply_known_profile() {
+        println!("Recognized {name}, using its known quirks and speed");
+    }
+}
+
+#[cfg(feature = "persist")]
+fn save_settings(chip8: &Chip8) {
+    if let Err(error) = chip8.save_settings() {
+        eprintln!("Couldn't save the game's settings: {error}");
+    }
+}
+
+/// Reapplies the settings the game was last run with and moves the ROM to the top of the recent ROMs
+#[cfg(feature = "persist")]
+fn remember_rom(chip8: &mut Chip8, path: &std::path::Path) {
+    chip8.load_settings();
+
+    if let Err(error) = chip8::recent::add_recent_rom(path) {
+        eprintln!("Couldn't save the recent ROMs: {error}");
+    }
+}
+
+/// Reads a ROM picked at runtime or dropped onto the window, checking it looks like a CHIP-8 program that fits in memory
+fn read_rom(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ch8")) {
+        return Err("only .ch8 files can be loaded".to_string());
+    }
+
+    let rom = std::fs::read(path).map_err(|error| error.to_string())?;
+
+    check_rom_size(&rom)?;
+
+    Ok(rom)
+}
+
+/// Reads a ROM piped into the program, which has no file name to check so only its size is validated
+fn read_stdin_rom() -> Result<Vec<u8>, String> {
+    let mut rom = Vec::new();
+    std::io::stdin().read_to_end(&mut rom).map_err(|error| error.to_string())?;
+
+    if rom.is_empty() {
+        return Err("nothing was piped in".to_string());
+    }
+
+    check_rom_size(&rom)?;
+
+    Ok(rom)
+}
+
+fn check_rom_size(rom: &[u8]) -> Result<(), String> {
+    if rom.len() > MAX_PROGRAM_SIZE {
+        return Err(format!("the ROM is {} bytes but at most {} fit in memory", rom.len(), MAX_PROGRAM_SIZE));
+    }
+
+    Ok(())
+}
+
+/// Draws with the GPU unless `--renderer software` is passed. With the software feature a machine without a usable GPU
+/// falls back to drawing on the CPU
+fn create_chip8(config: Chip8Config, window: &winit::window::Window) -> Result<Chip8, Chip8Error> {
+    let software = arg_value("--renderer").is_some_and(|renderer| renderer == "software");
+
+    #[cfg(feature = "software")]
+    {
+        let software_chip8 = |config| Chip8::from_config_with_backend(config, Box::new(SoftwareRenderer::new(window)?));
+
+        if software {
+            return software_chip8(config);
+        }
+
+        Chip8::from_config(config.clone(), window).or_else(|error| {
+            eprintln!("{error}, falling back to the software renderer");
+            software_chip8(config)
+        })
+    }
+
+    #[cfg(not(feature = "software"))]
+    {
+        if software {
+            eprintln!("--renderer software needs the software feature, rebuild with `--features software`");
+            std::process::exit(1);
+        }
+
+        Chip8::from_config(config, window)
+    }
+}
+
+/// Returns the value following `name` in the command line arguments
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    args.find(|arg| arg == name)?;
+    args.next()
+}
@@ -0,0 +1,257 @@
+//! Watching and playing a running game from other machines over TCP. The host calls `SpectatorServer::broadcast` and
+//! `InputServer::poll` once per frame: every connected spectator is sent the screen whenever it changed and the keys
+//! pressed by remote players are fed to the machine. `SpectatorClient` and `InputClient` are the other ends.
+//!
+//! Each frame is a 4 byte big endian length followed by that many bytes of pixels packed by `Display::to_packed_bits`,
+//! 256 in lowres and 1024 in hires. Input is sent as 2 byte packets, the key (0x0 - 0xF) and 1 if it was pressed or
+//! 0 if it was released
+
+use std::{collections::HashSet, io::{self, Read, Write}, net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs}};
+use crate::{chip8::Chip8, display::Display};
+
+/// Streams the screen to every spectator that connects
+pub struct SpectatorServer {
+    listener: TcpListener,
+    spectators: Vec<TcpStream>,
+    last_frame: Vec<u8> // The last frame sent, new spectators are sent the current one right away
+}
+
+impl SpectatorServer {
+    /// Starts listening for spectators, e.g. on `0.0.0.0:8008`. Port 0 picks a free one, see `local_addr`
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        // Accepting happens on the emulator's thread, it can't wait for spectators
+        listener.set_nonblocking(true)?;
+
+        Ok(Self { listener, spectators: Vec::new(), last_frame: Vec::new() })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Accepts spectators waiting to connect and sends the screen to everyone if it changed since the last call.
+    /// Doesn't even look at the screen while nobody is watching. Spectators that disconnected or fell so far behind
+    /// that their socket's buffer is full are dropped
+    pub fn broadcast(&mut self, chip8: &Chip8) {
+        let mut joined: Vec<TcpStream> = accept_all(&self.listener, "spectator").into_iter().map(|(stream, _)| stream).collect();
+
+        if self.spectators.is_empty() && joined.is_empty() {
+            return;
+        }
+
+        let frame = chip8.packed_display();
+        let changed = frame != self.last_frame;
+
+        if changed {
+            self.last_frame = frame;
+        }
+
+        let message = encode_frame(&self.last_frame);
+
+        if changed {
+            self.spectators.retain_mut(|stream| Self::send(stream, &message));
+        }
+
+        joined.retain_mut(|stream| Self::send(stream, &message));
+        self.spectators.append(&mut joined);
+    }
+
+    /// Whether the spectator is still connected
+    fn send(stream: &mut TcpStream, message: &[u8]) -> bool {
+        match stream.write_all(message) {
+            Ok(()) => true,
+            Err(error) => {
+                // Writes never wait, so a full buffer fails here too rather than stalling the emulator
+                info!("Dropped spectator {}: {error}", stream.peer_addr().map_or("?".to_string(), |address| address.to_string()));
+                false
+            }
+        }
+    }
+}
+
+/// Prefixes the packed pixels with their length
+fn encode_frame(frame: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(4 + frame.len());
+    message.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    message.extend_from_slice(frame);
+
+    message
+}
+
+/// Receives the screen from a `SpectatorServer`
+pub struct SpectatorClient {
+    stream: TcpStream
+}
+
+impl SpectatorClient {
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self { stream: TcpStream::connect(address)? })
+    }
+
+    /// Waits for the next frame and rebuilds it as a headless display, e.g. to print it with `Display::to_ascii`
+    pub fn read_frame(&mut self) -> io::Result<Display> {
+        let mut len = [0; 4];
+        self.stream.read_exact(&mut len)?;
+
+        let len = u32::from_be_bytes(len) as usize;
+
+        // Nothing larger is ever sent, anything else is a corrupted stream or not a spectator server at all
+        if len > Display::MAX_WIDTH * Display::MAX_HEIGHT / 8 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("a frame can't be {len} bytes")));
+        }
+
+        let mut frame = vec![0; len];
+        self.stream.read_exact(&mut frame)?;
+
+        let mut display = Display::headless();
+        display.set_packed_bits(&frame).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        Ok(display)
+    }
+}
+
+/// Accepts connections without waiting, logging whatever goes wrong
+fn accept_all(listener: &TcpListener, kind: &str) -> Vec<(TcpStream, SocketAddr)> {
+    let mut accepted = Vec::new();
+
+    loop {
+        match listener.accept() {
+            Ok((stream, address)) => match stream.set_nonblocking(true).and_then(|()| stream.set_nodelay(true)) {
+                Ok(()) => {
+                    info!("Accepted {kind} {address}");
+                    accepted.push((stream, address));
+                },
+                Err(error) => warn!("Couldn't set up {kind} {address}: {error}")
+            },
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+            Err(error) => {
+                warn!("Couldn't accept a {kind}: {error}");
+                break;
+            }
+        }
+    }
+
+    accepted
+}
+
+/// A remote player and the keys it's holding down
+struct Player {
+    stream: TcpStream,
+    address: SocketAddr,
+    received: Vec<u8>, // Bytes of a packet that hasn't fully arrived yet
+    held: HashSet<u8> // Released when the player disconnects since the releases won't arrive anymore
+}
+
+/// Lets remote players press keys
+pub struct InputServer {
+    listener: TcpListener,
+    players: Vec<Player>
+}
+
+impl InputServer {
+    /// Starts listening for players, see `SpectatorServer::bind`
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self { listener, players: Vec::new() })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Accepts players waiting to connect and presses and releases the keys they sent since the last call.
+    /// Packets for keys that don't exist are logged and skipped, players that disconnected have their keys released
+    pub fn poll(&mut self, chip8: &mut Chip8) {
+        self.players.extend(accept_all(&self.listener, "player").into_iter().map(|(stream, address)| Player {
+            stream,
+            address,
+            received: Vec::new(),
+            held: HashSet::new()
+        }));
+
+        self.players.retain_mut(|player| {
+            let connected = Self::receive(player);
+
+            for packet in player.received.chunks_exact(2) {
+                match (packet[0], packet[1]) {
+                    (key @ 0x0..=0xF, 1) => {
+                        player.held.insert(key);
+                        chip8.press_key(key);
+                    },
+                    (key @ 0x0..=0xF, 0) => {
+                        player.held.remove(&key);
+                        chip8.release_key(key);
+                    },
+                    (key, state) => warn!("Player {} sent a malformed packet: key {key:#04X}, state {state}", player.address)
+                }
+            }
+
+            // Keeps the first byte of a packet whose second one hasn't arrived
+            let complete = player.received.len() / 2 * 2;
+            player.received.drain(..complete);
+
+            if !connected {
+                info!("Player {} disconnected", player.address);
+
+                for &key in &player.held {
+                    chip8.release_key(key);
+                }
+            }
+
+            connected
+        });
+    }
+
+    /// Reads everything the player sent so far, returns whether it's still connected
+    fn receive(player: &mut Player) -> bool {
+        let mut buffer = [0; 64];
+
+        loop {
+            match player.stream.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(len) => player.received.extend_from_slice(&buffer[..len]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+                Err(error) => {
+                    warn!("Lost player {}: {error}", player.address);
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Presses keys on an `InputServer`'s machine
+pub struct InputClient {
+    stream: TcpStream
+}
+
+impl InputClient {
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        // Key presses are tiny and should arrive right away rather than be batched
+        stream.set_nodelay(true)?;
+
+        Ok(Self { stream })
+    }
+
+    /// Presses a key on the keypad (0x0 to 0xF)
+    pub fn press(&mut self, key: u8) -> io::Result<()> {
+        self.stream.write_all(&[key, 1])
+    }
+
+    pub fn release(&mut self, key: u8) -> io::Result<()> {
+        self.stream.write_all(&[key, 0])
+    }
+}
@@ -0,0 +1,85 @@
+//! Draws text into a texture laid over the display, used to show the machine's state while debugging.
+//! The texture is twice the CHIP-8's resolution so a few lines of text fit without covering the whole screen
+
+pub const WIDTH: usize = 128;
+pub const HEIGHT: usize = 64;
+
+/// Texel values of the overlay texture, the shader shows the display through `CLEAR`
+pub const CLEAR: u8 = 0;
+pub const BACKDROP: u8 = 0x80;
+pub const TEXT: u8 = 0xFF;
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 5;
+
+// Glyphs are spaced by a column and a row of backdrop so they don't run together
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+/// The rows of a character's glyph, with the leftmost pixel in the most significant bit of the high nibble.
+/// Only the characters the debug overlay and the crash screen print are covered, anything else is drawn as a blank
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        '0' => [0xF0, 0x90, 0x90, 0x90, 0xF0],
+        '1' => [0x20, 0x60, 0x20, 0x20, 0x70],
+        '2' => [0xF0, 0x10, 0xF0, 0x80, 0xF0],
+        '3' => [0xF0, 0x10, 0xF0, 0x10, 0xF0],
+        '4' => [0x90, 0x90, 0xF0, 0x10, 0x10],
+        '5' => [0xF0, 0x80, 0xF0, 0x10, 0xF0],
+        '6' => [0xF0, 0x80, 0xF0, 0x90, 0xF0],
+        '7' => [0xF0, 0x10, 0x20, 0x40, 0x40],
+        '8' => [0xF0, 0x90, 0xF0, 0x90, 0xF0],
+        '9' => [0xF0, 0x90, 0xF0, 0x10, 0xF0],
+        'A' => [0xF0, 0x90, 0xF0, 0x90, 0x90],
+        'B' => [0xE0, 0x90, 0xE0, 0x90, 0xE0],
+        'C' => [0xF0, 0x80, 0x80, 0x80, 0xF0],
+        'D' => [0xE0, 0x90, 0x90, 0x90, 0xE0],
+        'E' => [0xF0, 0x80, 0xF0, 0x80, 0xF0],
+        'F' => [0xF0, 0x80, 0xF0, 0x80, 0x80],
+        'H' => [0x90, 0x90, 0xF0, 0x90, 0x90],
+        'I' => [0x70, 0x20, 0x20, 0x20, 0x70],
+        'O' => [0x60, 0x90, 0x90, 0x90, 0x60],
+        'P' => [0xF0, 0x90, 0xF0, 0x80, 0x80],
+        'R' => [0xE0, 0x90, 0xE0, 0xA0, 0x90],
+        'S' => [0xF0, 0x80, 0xF0, 0x10, 0xF0],
+        'T' => [0xF0, 0x40, 0x40, 0x40, 0x40],
+        'V' => [0x90, 0x90, 0x90, 0x90, 0x60],
+        ':' => [0x00, 0x40, 0x00, 0x40, 0x00],
+        _ => [0x00; GLYPH_HEIGHT]
+    }
+}
+
+/// Draws every line of the text onto a backdrop starting at the top left, one texel per byte.
+/// Whatever doesn't fit in the texture is cut off
+pub fn rasterize(text: &str) -> Vec<u8> {
+    let mut texels = vec![CLEAR; WIDTH * HEIGHT];
+
+    for (row, line) in text.lines().enumerate() {
+        for (column, character) in line.chars().enumerate() {
+            let cell_x = column * CELL_WIDTH;
+            let cell_y = row * CELL_HEIGHT;
+
+            // The backdrop also covers the spacing to the left and above the first glyph so the text has a border
+            for y in cell_y..=cell_y + CELL_HEIGHT {
+                for x in cell_x..=cell_x + CELL_WIDTH {
+                    if x < WIDTH && y < HEIGHT {
+                        texels[y * WIDTH + x] = BACKDROP;
+                    }
+                }
+            }
+
+            for (glyph_y, glyph_row) in glyph(character).into_iter().enumerate() {
+                for glyph_x in 0..GLYPH_WIDTH {
+                    let x = cell_x + 1 + glyph_x;
+                    let y = cell_y + 1 + glyph_y;
+
+                    if glyph_row >> (7 - glyph_x) & 1 != 0 && x < WIDTH && y < HEIGHT {
+                        texels[y * WIDTH + x] = TEXT;
+                    }
+                }
+            }
+        }
+    }
+
+    texels
+}
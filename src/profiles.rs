@@ -0,0 +1,78 @@
+use std::path::Path;
+use crate::quirks::{QuirkPreset, Quirks};
+
+/// The quirks and speed a known ROM needs to run correctly. A ROM is recognized by its hash, or by its title when the
+/// hash isn't known since the same game is often passed around in slightly different dumps
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// The ROM's `Chip8::rom_hash`
+    #[serde(default)]
+    pub hash: Option<u64>,
+    /// Matched against the ROM's file name without its extension and anything in brackets or parentheses after the
+    /// title, ignoring case. E.g. `Blinky` matches `BLINKY.ch8` and `Blinky [Hans Christian Egeberg, 1991].ch8`
+    #[serde(default)]
+    pub title: Option<String>,
+    pub quirks: Quirks,
+    pub cycles_per_frame: u32
+}
+
+impl Profile {
+    /// Whether this is the profile of a ROM with this hash
+    pub fn matches_hash(&self, hash: u64) -> bool {
+        self.hash == Some(hash)
+    }
+
+    /// Whether this is the profile of a ROM with this file name, see `title`
+    pub fn matches_name(&self, rom_name: &str) -> bool {
+        let stem = rom_name.rsplit_once('.').map_or(rom_name, |(stem, _)| stem);
+        let title = stem.split(['[', '(']).next().unwrap_or_default().trim();
+
+        self.title.as_deref().is_some_and(|expected| expected.eq_ignore_ascii_case(title))
+    }
+}
+
+/// The hash of `examples/ibm_logo.ch8`
+const IBM_LOGO_HASH: u64 = 0x64e45391ba0238a1;
+
+/// The profiles that ship with the emulator. Games are keyed by title, taken from the CHIP-8 compatibility lists that
+/// name the quirks they rely on, and ROMs whose file the hash has been checked against by hash
+pub fn built_in() -> Vec<Profile> {
+    let by_hash = |name: &str, hash, quirks, cycles_per_frame| Profile {
+        name: name.to_string(), hash: Some(hash), title: None, quirks, cycles_per_frame
+    };
+    let by_title = |name: &str, quirks, cycles_per_frame| Profile {
+        name: name.to_string(), hash: None, title: Some(name.to_string()), quirks, cycles_per_frame
+    };
+
+    vec![
+        by_hash("IBM Logo", IBM_LOGO_HASH, Quirks::default(), 60),
+        // Shifts VX in place like the HP48 interpreters did
+        by_title("Space Invaders", Quirks { shift_uses_vy: false, ..Quirks::default() }, 60),
+        // Shifts VX in place and leaves I alone on FX55/FX65
+        by_title("Blinky", Quirks { shift_uses_vy: false, load_store_increments_i: false, ..Quirks::default() }, 60),
+        by_title("Spacefight 2091!", Quirks::preset(QuirkPreset::SuperChip), 60),
+        by_title("Ant", Quirks::preset(QuirkPreset::SuperChip), 60)
+    ]
+}
+
+/// Parses a JSON array of profiles, hashes are written as numbers and either the hash or the title can be left out
+pub fn from_json(json: &str) -> Result<Vec<Profile>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Reads and parses a profiles JSON file, see `from_json`
+pub fn from_file(path: &Path) -> Result<Vec<Profile>, String> {
+    let json = std::fs::read_to_string(path).map_err(|error| format!("couldn't read {}: {error}", path.display()))?;
+
+    from_json(&json).map_err(|error| format!("couldn't parse {}: {error}", path.display()))
+}
+
+/// The profiles in `profiles.json` in the config directory, empty if there's no such file
+#[cfg(feature = "persist")]
+pub fn load_user_profiles() -> Result<Vec<Profile>, String> {
+    match crate::settings::config_file("profiles.json") {
+        Some(path) if path.exists() => from_file(&path),
+        _ => Ok(Vec::new())
+    }
+}
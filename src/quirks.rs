@@ -0,0 +1,118 @@
+/// Behaviors that differ between CHIP-8 interpreters. ROMs written for one interpreter may misbehave on another,
+/// so these let the emulator match whichever the ROM expects.
+/// The defaults match the behavior of the original COSMAC VIP interpreter for the shifts and `BNNN`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)] // Quirks added later are left at their default in older saved settings and profiles
+pub struct Quirks {
+    /// `8XY6`/`8XYE` copy VY into VX before shifting. When off VX is shifted in place (SCHIP)
+    pub shift_uses_vy: bool,
+    /// `BNNN` jumps to `XNN + VX` instead of `NNN + V0` (SCHIP)
+    pub jump_uses_vx: bool,
+    /// `DXYN` waits for the next 60Hz tick before execution continues, limiting draws to one per frame like the
+    /// COSMAC VIP did while waiting for the vertical blank. Some games rely on it to avoid flicker, see
+    /// `Chip8::set_max_draws_per_frame` for a looser limit
+    pub display_wait: bool,
+    /// The pc wraps around to 0x000 when it goes past the end of memory instead of the machine failing with an out of bounds error.
+    /// The 4KB COSMAC VIP didn't fully decode addresses so RAM repeated past 0xFFF and execution carried on from the
+    /// start of it, most later interpreters have no memory there and stop
+    pub pc_wrap: bool,
+    /// `FX55`/`FX65` leave I pointing past the last register stored or loaded (I += X + 1) like the COSMAC VIP.
+    /// When off I is left unchanged (SCHIP)
+    pub load_store_increments_i: bool,
+    /// `DXYN` wraps the part of a sprite that goes past the right edge around to the left edge. When off it's clipped,
+    /// like on interpreters that only wrap vertically
+    pub wrap_x: bool,
+    /// `DXYN` wraps the part of a sprite that goes past the bottom edge around to the top edge. When off it's clipped
+    pub wrap_y: bool,
+    /// `FX1E` sets VF to 1 when I goes past the end of memory and to 0 otherwise, like the Amiga interpreter did.
+    /// When off VF is left alone, which is what most games expect
+    pub fx1e_sets_vf: bool,
+    /// In lowres the SCHIP scrolls move by half as many pixels: `00CN` scrolls N/2 rows (rounded up) and `00FB`/`00FC`
+    /// scroll 2 columns. SUPER-CHIP 1.1 drew lowres pixels as 2x2 blocks of the HP 48's screen and scrolled the screen's
+    /// own pixels. When off the scrolls move the same number of pixels in both resolutions
+    pub lowres_scroll_halved: bool
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            shift_uses_vy: true,
+            jump_uses_vx: false,
+            display_wait: false,
+            pc_wrap: false,
+            load_store_increments_i: false,
+            wrap_x: true,
+            wrap_y: true,
+            fx1e_sets_vf: false,
+            lowres_scroll_halved: false
+        }
+    }
+}
+
+/// Named sets of quirks matching a whole platform, see `Quirks::preset`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuirkPreset {
+    /// The original interpreter
+    CosmacVip,
+    /// SUPER-CHIP 1.1 on the HP 48
+    SuperChip,
+    /// Octo's XO-CHIP
+    XoChip,
+    /// What most recent emulators do, the same as `Quirks::default`
+    Modern
+}
+
+impl Quirks {
+    /// The full set of quirks a platform needs:
+    ///
+    /// | Quirk                     | CosmacVip | SuperChip | XoChip | Modern |
+    /// |---------------------------|-----------|-----------|--------|--------|
+    /// | `shift_uses_vy`           | on        | off       | on     | on     |
+    /// | `jump_uses_vx`            | off       | on        | off    | off    |
+    /// | `display_wait`            | on        | off       | off    | off    |
+    /// | `pc_wrap`                 | on        | off       | off    | off    |
+    /// | `load_store_increments_i` | on        | off       | on     | off    |
+    /// | `wrap_x`                  | off       | off       | on     | on     |
+    /// | `wrap_y`                  | off       | off       | on     | on     |
+    /// | `fx1e_sets_vf`            | off       | off       | off    | off    |
+    /// | `lowres_scroll_halved`    | off       | on        | off    | off    |
+    pub fn preset(preset: QuirkPreset) -> Self {
+        match preset {
+            QuirkPreset::CosmacVip => Self {
+                shift_uses_vy: true,
+                jump_uses_vx: false,
+                display_wait: true,
+                pc_wrap: true,
+                load_store_increments_i: true,
+                wrap_x: false,
+                wrap_y: false,
+                fx1e_sets_vf: false,
+                lowres_scroll_halved: false
+            },
+            QuirkPreset::SuperChip => Self {
+                shift_uses_vy: false,
+                jump_uses_vx: true,
+                display_wait: false,
+                pc_wrap: false,
+                load_store_increments_i: false,
+                wrap_x: false,
+                wrap_y: false,
+                fx1e_sets_vf: false,
+                lowres_scroll_halved: true
+            },
+            QuirkPreset::XoChip => Self {
+                shift_uses_vy: true,
+                jump_uses_vx: false,
+                display_wait: false,
+                pc_wrap: false,
+                load_store_increments_i: true,
+                wrap_x: true,
+                wrap_y: true,
+                fx1e_sets_vf: false,
+                lowres_scroll_halved: false
+            },
+            QuirkPreset::Modern => Self::default()
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+/// How many ROMs are remembered, the oldest is forgotten when another one is loaded
+pub const MAX_RECENT_ROMS: usize = 8;
+
+/// Where the list is stored, None if the OS has no config directory for the user
+fn recent_roms_path() -> Option<PathBuf> {
+    crate::settings::config_file("recent_roms.json")
+}
+
+/// The paths of the recently loaded ROMs, most recent first. Empty if none were saved or the file couldn't be read.
+/// The settings each ROM ran with are kept by the settings module
+pub fn recent_roms() -> Vec<PathBuf> {
+    recent_roms_path()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Moves the ROM to the front of the list and saves it
+pub fn add_recent_rom(path: &Path) -> std::io::Result<()> {
+    let file = recent_roms_path()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config directory"))?;
+
+    let mut roms = recent_roms();
+    roms.retain(|rom| rom != path);
+    roms.insert(0, path.to_path_buf());
+    roms.truncate(MAX_RECENT_ROMS);
+
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(file, serde_json::to_vec_pretty(&roms)?)
+}
@@ -0,0 +1,862 @@
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use crate::{display::{self, Display, DisplayBackend, Palette, Pixels, PresentMode}, error::Chip8Error, overlay};
+
+// The screen is drawn as a single quad sampling a MAX_WIDTH x MAX_HEIGHT R8 texture with nearest filtering,
+// rather than one instanced quad per pixel. A frame uploads one byte per pixel (8KB at 128x64) instead of a 12 byte
+// instance per pixel (96KB) and issues 6 vertices instead of 6 per pixel. The `screen upload data` benchmark compares
+// generating both on the CPU: the texture takes about half as long (9.5µs against 19µs when it was written), even
+// though it combines both planes. The GPU side isn't benchmarked since that needs an adapter
+const PIXEL_VERTICES: [f32; 12] = [
+  // first triangle: top left -> bottom left -> top right
+  0.0, 1.0,
+  0.0, 0.0,
+  1.0, 1.0,
+  // second triangle: bottom left -> bottom right -> top right
+  0.0, 0.0,
+  1.0, 0.0,
+  1.0, 1.0
+];
+
+
+/// Rendering options read by the fragment shader. Matches `Effects` in shaders.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+struct Effects {
+    scanlines: u32, // Darkens the bottom of every row of pixels
+    grid: u32, // Darkens the edges of every pixel
+    overlay: u32, // Draws the overlay texture over the display
+    dimmed: u32, // Darkens everything, shown while paused
+    palette: [[f32; 4]; 4], // The colors pixels are shown in, indexed by the screen texture, see `Palette`
+    width: u32, // How much of the screen texture holds the display
+    height: u32,
+    _padding: [u32; 2] // Uniform buffers must be a multiple of 16 bytes
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self {
+            scanlines: 0,
+            grid: 0,
+            overlay: 0,
+            dimmed: 0,
+            palette: Display::DEFAULT_PALETTE.map(|color| {
+                let [r, g, b] = color.map(|channel| channel as f32 / 255.0);
+                [r, g, b, 1.0]
+            }),
+            width: Display::WIDTH as u32,
+            height: Display::HEIGHT as u32,
+            _padding: [0; 2]
+        }
+    }
+}
+
+/// Controls which GPU adapter the display renders with
+#[derive(Clone, Copy, Debug)]
+pub struct GpuConfig {
+    /// `HighPerformance` picks the discrete GPU on hybrid graphics machines, `LowPower` the integrated one
+    pub power_preference: wgpu::PowerPreference,
+    /// Use a software adapter even if a hardware one is available
+    pub force_fallback_adapter: bool
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::LowPower,
+            force_fallback_adapter: false
+        }
+    }
+}
+
+/// The GPU objects renderers can share, so several emulators can draw to one or more windows with a single device
+pub struct GpuContext {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue
+}
+
+impl GpuContext {
+    /// Opens a device on an adapter that can present to `window`, failing if there's no such adapter or it can't
+    /// open a device
+    pub fn new(window: &winit::window::Window, gpu_config: &GpuConfig) -> Result<Arc<Self>, Chip8Error> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            dx12_shader_compiler: Default::default()
+        });
+
+        // Only used to pick a compatible adapter, each renderer creates its own surface.
+        // Fails when no backend can draw to windows at all, e.g. no Vulkan/Metal/DX12/GL driver is installed
+        let surface = unsafe { instance.create_surface(window) }
+            .map_err(|error| Chip8Error::GpuInit(format!("couldn't create a surface for the window: {error}")))?;
+
+        // None when there's no GPU, or none whose driver can present to this window (e.g. a remote desktop or a VM
+        // without 3D acceleration). `force_fallback_adapter` can still find a software adapter in that case
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: gpu_config.power_preference,
+            force_fallback_adapter: gpu_config.force_fallback_adapter,
+            compatible_surface: Some(&surface)
+        })).ok_or_else(|| Chip8Error::GpuInit("no compatible GPU found".to_string()))?;
+
+        drop(surface);
+
+        let adapter_info = adapter.get_info();
+        info!("Using adapter {} ({:?})", adapter_info.name, adapter_info.backend);
+
+        // Fails when the driver doesn't support the default limits, usually a very old GPU or an outdated driver
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("Chip 8 device and queue"),
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default()
+        }, None)).map_err(|error| Chip8Error::GpuInit(format!("{} couldn't open a device: {error}", Self::describe(&adapter))))?;
+
+        Ok(Arc::new(Self { instance, adapter, device, queue }))
+    }
+
+    /// The adapter's name, backend and driver, for error messages
+    fn describe(adapter: &wgpu::Adapter) -> String {
+        let info = adapter.get_info();
+
+        format!("{} ({:?}, driver {} {})", info.name, info.backend, info.driver, info.driver_info)
+    }
+}
+
+/// Draws the display's pixels to a window with wgpu, the `DisplayBackend` of windowed displays
+pub struct WgpuDisplay {
+    uploaded_pixels: Option<[Pixels; 2]>, // Both planes currently held by the screen texture, None if it has to be uploaded
+    second_plane: Pixels, // Set by `set_second_plane`, uploaded along with the pixels on the next render
+    texture_uploads: u64,
+    surface: wgpu::Surface,
+    surface_config: wgpu::SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>, // The present modes supported by the surface
+    context: Arc<GpuContext>,
+    pipeline: wgpu::RenderPipeline,
+    capture_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    screen_texture: wgpu::Texture,
+    overlay_texture: wgpu::Texture, // Debugging text drawn over the display, see the overlay module
+    #[cfg_attr(not(feature = "debug"), allow(dead_code))]
+    ghost_texture: wgpu::Texture, // Tints pixels recently turned off by a collision, see `Display::set_ghost_pixels`
+    screen_bind_group: wgpu::BindGroup,
+    effects: Effects,
+    effects_buffer: wgpu::Buffer,
+}
+
+impl WgpuDisplay {
+    pub fn new(window: &winit::window::Window, gpu_config: &GpuConfig) -> Result<Self, Chip8Error> {
+        Self::with_context(GpuContext::new(window, gpu_config)?, window)
+    }
+
+    /// Draws to `window` with a device that may be shared with other renderers. The window has to be presentable
+    /// by the context's adapter, which is the case for any window on the same screen as the one the context was made for.
+    /// Fails if the surface or pipelines can't be created, e.g. on drivers missing a feature wgpu needs
+    pub fn with_context(context: Arc<GpuContext>, window: &winit::window::Window) -> Result<Self, Chip8Error> {
+        let surface = unsafe { context.instance.create_surface(window) }
+            .map_err(|error| Chip8Error::GpuInit(format!("couldn't create a surface for the window: {error}")))?;
+        let device = &context.device;
+
+        let surface_caps = surface.get_capabilities(&context.adapter);
+
+        // A window on another screen than the one the context's adapter was picked for may not be presentable by it
+        if surface_caps.formats.is_empty() || surface_caps.alpha_modes.is_empty() {
+            return Err(Chip8Error::GpuInit(format!("{} can't present to the window", GpuContext::describe(&context.adapter))));
+        }
+
+        // Validation errors would otherwise panic in wgpu's default error handler. They come from drivers that claim
+        // support for something they can't do, e.g. a surface format or a shader feature
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let window_size = window.inner_size();
+
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: Self::choose_surface_format(&surface_caps.formats),
+            width: window_size.width,
+            height: window_size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![]
+        };
+
+        surface.configure(device, &surface_config);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pixel vertex buffer"),
+            contents: bytemuck::bytes_of(&PIXEL_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST
+        });
+
+        let screen_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Screen texture"),
+            size: Self::screen_extent(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[]
+        });
+
+        let screen_view = screen_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let overlay_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Overlay texture"),
+            size: Self::overlay_extent(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[]
+        });
+
+        let overlay_view = overlay_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Same layout as the screen texture, holds how strongly each pixel is tinted after a collision turned it off
+        let ghost_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Ghost texture"),
+            size: Self::screen_extent(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[]
+        });
+
+        let ghost_view = ghost_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Nearest filtering keeps the pixels sharp no matter how much the window is stretched
+        let screen_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Screen sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let effects = Effects::default();
+
+        let effects_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Effects uniform buffer"),
+            contents: bytemuck::bytes_of(&effects),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+        });
+
+        let screen_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Screen bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                }
+            ]
+        });
+
+        let screen_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Screen bind group"),
+            layout: &screen_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&screen_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&screen_sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effects_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&overlay_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&ghost_view)
+                }
+            ]
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chip8 pipeline layout"),
+            bind_group_layouts: &[&screen_bind_group_layout],
+            push_constant_ranges: &[]
+        });
+
+        let shader_src = wgpu::include_wgsl!("./shaders.wgsl");
+        let shader_module = device.create_shader_module(shader_src);
+
+        let pipeline = Self::create_pipeline(device, &pipeline_layout, &shader_module, surface_config.format);
+
+        // Captured frames are rendered with their own pipeline since the surface's format may not be RGBA
+        let capture_format = Self::capture_format(surface_config.format);
+        let capture_pipeline = Self::create_pipeline(device, &pipeline_layout, &shader_module, capture_format);
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(Chip8Error::GpuInit(format!("{} failed to set up rendering: {error}", GpuContext::describe(&context.adapter))));
+        }
+
+        Ok(WgpuDisplay { 
+            uploaded_pixels: None,
+            second_plane: [0; Display::MAX_HEIGHT],
+            texture_uploads: 0,
+            surface,
+            surface_config,
+            present_modes: surface_caps.present_modes,
+            context,
+            pipeline,
+            capture_pipeline,
+            vertex_buffer,
+            screen_texture,
+            overlay_texture,
+            ghost_texture,
+            screen_bind_group,
+            effects,
+            effects_buffer,
+        })
+    }
+
+    /// The RGBA format captured frames are drawn in. It's sRGB only if the surface is so captures look exactly like the window
+    fn capture_format(surface_format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        if surface_format.is_srgb() {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader_module: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Chip 8 pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader_module,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout{
+                    array_stride: 2 * 4,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2]
+                }]
+            },
+            primitive: wgpu::PrimitiveState { 
+                topology: wgpu::PrimitiveTopology::TriangleList, 
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Cw, 
+                cull_mode: Some(wgpu::Face::Back), 
+                unclipped_depth: false, 
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+            fragment: Some(wgpu::FragmentState {
+                module: shader_module, 
+                entry_point: "fs_main", 
+                targets: &[Some(wgpu::ColorTargetState {
+                    // Must match the format of the texture being drawn to or the pipeline fails validation
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL
+                })] 
+            }),
+            multiview: None
+        })
+    }
+
+    /// Darkens the bottom of every row of pixels to imitate a CRT
+    pub fn set_scanlines(&mut self, enabled: bool) {
+        self.effects.scanlines = enabled as u32;
+        self.update_effects();
+    }
+
+    /// Darkens the edges of every pixel so they can be told apart
+    pub fn set_grid(&mut self, enabled: bool) {
+        self.effects.grid = enabled as u32;
+        self.update_effects();
+    }
+
+    /// Sets the colors of pixels that are on and off, given as sRGB
+    pub fn set_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) {
+        self.effects.palette[1] = self.shader_color(foreground);
+        self.effects.palette[0] = self.shader_color(background);
+        self.update_effects();
+    }
+
+    /// Sets all four colors, see `Palette`
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.effects.palette = palette.map(|color| self.shader_color(color));
+        self.update_effects();
+    }
+
+    /// Shows the pixels of the second plane from the next render on, see `Display::set_second_plane`
+    pub fn set_second_plane(&mut self, pixels: &Pixels) {
+        self.second_plane = *pixels;
+    }
+
+    /// Converts an sRGB color into what the shader should output. sRGB surfaces expect linear colors
+    /// and encode them on write, so the color has to be decoded first or it would come out too bright
+    fn shader_color(&self, color: [u8; 3]) -> [f32; 4] {
+        let srgb = self.surface_config.format.is_srgb();
+
+        let channel = |value: u8| {
+            let value = value as f32 / 255.0;
+
+            if !srgb {
+                value
+            } else if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        [channel(color[0]), channel(color[1]), channel(color[2]), 1.0]
+    }
+
+    /// Darkens everything, used to show the emulator is paused
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        self.effects.dimmed = dimmed as u32;
+        self.update_effects();
+    }
+
+    /// Draws the text over the display, or hides the overlay when None
+    pub fn set_overlay(&mut self, text: Option<&str>) {
+        if let Some(text) = text {
+            self.context.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.overlay_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All
+                },
+                &overlay::rasterize(text),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(overlay::WIDTH as u32),
+                    rows_per_image: Some(overlay::HEIGHT as u32)
+                },
+                Self::overlay_extent()
+            );
+        }
+
+        self.effects.overlay = text.is_some() as u32;
+        self.update_effects();
+    }
+
+    fn update_effects(&mut self) {
+        self.context.queue.write_buffer(&self.effects_buffer, 0, bytemuck::bytes_of(&self.effects));
+    }
+
+    /// Picks the first sRGB format the surface supports, falling back to whatever it lists first.
+    /// The driver's ordering varies between machines so relying on it would make colors differ between them
+    fn choose_surface_format(formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+        formats.iter()
+            .copied()
+            .find(|format| format.is_srgb())
+            .unwrap_or(formats[0])
+    }
+
+    /// The format the window surface was configured with
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.surface_config.format
+    }
+
+    /// Changes how frames are presented to the window, falling back to `Fifo` (vsync, supported everywhere)
+    /// if the surface doesn't support the requested mode. Returns the mode that ended up being used.
+    /// Note that `Immediate` doesn't wait for the vertical blank so it may tear
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let present_mode = if self.present_modes.contains(&present_mode) {present_mode} else {wgpu::PresentMode::Fifo};
+
+        self.surface_config.present_mode = present_mode;
+        self.surface.configure(&self.context.device, &self.surface_config);
+
+        present_mode
+    }
+
+    /// Turns vsync on or off. When turning it off `Mailbox` is preferred since unlike `Immediate` it doesn't tear
+    pub fn set_vsync(&mut self, vsync: bool) -> wgpu::PresentMode {
+        if vsync {
+            self.set_present_mode(wgpu::PresentMode::Fifo)
+        } else if self.present_modes.contains(&wgpu::PresentMode::Mailbox) {
+            self.set_present_mode(wgpu::PresentMode::Mailbox)
+        } else {
+            self.set_present_mode(wgpu::PresentMode::Immediate)
+        }
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        self.surface_config.present_mode == wgpu::PresentMode::Fifo
+    }
+
+    pub fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
+        self.surface_config.width = new_size.width;
+        self.surface_config.height = new_size.height;
+
+        self.surface.configure(&self.context.device, &self.surface_config);
+    }
+
+    /// The screen texture is allocated at the largest resolution, smaller ones only use its top left corner
+    fn screen_extent() -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: Display::MAX_WIDTH as u32,
+            height: Display::MAX_HEIGHT as u32,
+            depth_or_array_layers: 1
+        }
+    }
+
+    /// Changes how many pixels of the screen texture are shown. Every pixel is uploaded again on the next render
+    pub fn set_resolution(&mut self, width: usize, height: usize) {
+        self.effects.width = width as u32;
+        self.effects.height = height as u32;
+        self.update_effects();
+
+        self.uploaded_pixels = None;
+    }
+
+    fn overlay_extent() -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: overlay::WIDTH as u32,
+            height: overlay::HEIGHT as u32,
+            depth_or_array_layers: 1
+        }
+    }
+
+    /// Converts both planes into one byte per pixel ready to be uploaded to the screen texture. Each byte is the
+    /// pixel's palette index spread over the whole range (0, 85, 170 or 255) so the shader reads it back as index / 3
+    pub(crate) fn gen_texture_data(pixels: &Pixels, second_plane: &Pixels) -> [u8; Display::MAX_WIDTH * Display::MAX_HEIGHT] {
+        let mut texels = [0; Display::MAX_WIDTH * Display::MAX_HEIGHT];
+
+        for y in 0..Display::MAX_HEIGHT {
+            for x in 0..Display::MAX_WIDTH {
+                texels[Display::MAX_WIDTH * y + x] = display::color_index(pixels, second_plane, x, y) as u8 * 85;
+            }
+        }
+
+        texels
+    }
+
+    /// Uploads the pixels to the screen texture, skipping the upload if neither plane changed since the last one
+    fn upload_pixels(&mut self, pixels: &Pixels) {
+        let planes = [*pixels, self.second_plane];
+
+        if self.uploaded_pixels == Some(planes) {
+            return;
+        }
+
+        self.context.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.screen_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All
+            },
+            &Self::gen_texture_data(pixels, &self.second_plane),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(Display::MAX_WIDTH as u32),
+                rows_per_image: Some(Display::MAX_HEIGHT as u32)
+            },
+            Self::screen_extent()
+        );
+
+        self.uploaded_pixels = Some(planes);
+        self.texture_uploads += 1;
+    }
+
+    /// Uploads how strongly each pixel is tinted red, one byte per pixel laid out like the screen texture
+    #[cfg(feature = "debug")]
+    pub fn upload_ghosts(&mut self, intensities: &[u8]) {
+        self.context.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.ghost_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All
+            },
+            intensities,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(Display::MAX_WIDTH as u32),
+                rows_per_image: Some(Display::MAX_HEIGHT as u32)
+            },
+            Self::screen_extent()
+        );
+    }
+
+    /// Returns how many times the screen texture has been uploaded to the GPU. Useful to check redundant uploads are skipped
+    pub fn texture_uploads(&self) -> u64 {
+        self.texture_uploads
+    }
+
+    /// Draws the pixels to the window. Returns whether the frame was actually presented.
+    /// A lost or outdated surface is reconfigured and a timed out one is skipped, in both cases the frame should be drawn
+    /// again on the next call. Only running out of memory is reported as an error
+    pub fn render(&mut self, pixels: &Pixels) -> Result<bool, wgpu::SurfaceError> {
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(&self.context.device, &self.surface_config);
+                return Ok(false);
+            },
+            Err(wgpu::SurfaceError::Timeout) => return Ok(false),
+            Err(error) => return Err(error)
+        };
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.upload_pixels(pixels);
+
+        let mut command_encoder = self.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chip 8 command enconder")
+        });
+
+        self.draw_screen(&mut command_encoder, &frame_view, &self.pipeline);
+
+        self.context.queue.submit(Some(command_encoder.finish()));
+        frame.present();
+
+        Ok(true)
+    }
+
+    fn draw_screen(&self, command_encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, pipeline: &wgpu::RenderPipeline) {
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Chip 8 Render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true
+                }
+            })],
+            depth_stencil_attachment: None
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.screen_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..6, 0..1);
+    }
+
+    /// The size of the frames drawn by `render` and returned by `read_pixels`
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        winit::dpi::PhysicalSize::new(self.surface_config.width, self.surface_config.height)
+    }
+
+    /// Draws the current frame into an offscreen texture instead of the window and reads it back.
+    /// Returns `size().width * size().height` RGBA pixels, row by row starting from the top left. Fails while the
+    /// window has no area, e.g. when it's minimized, or if the frame can't be read back
+    pub fn read_pixels(&mut self, pixels: &Pixels) -> Result<Vec<u8>, Chip8Error> {
+        let size = self.size();
+
+        if size.width == 0 || size.height == 0 {
+            return Err(Chip8Error::FrameCapture(format!("the window is {}x{}", size.width, size.height)));
+        }
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1
+        };
+
+        let capture_texture = self.context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::capture_format(self.surface_config.format),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        });
+        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Rows copied out of a texture must start at multiples of 256 bytes so each one is padded up to that
+        let unpadded_bytes_per_row = size.width * 4;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback_buffer = self.context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture readback buffer"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false
+        });
+
+        self.upload_pixels(pixels);
+
+        let mut command_encoder = self.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chip 8 capture command encoder")
+        });
+
+        self.draw_screen(&mut command_encoder, &capture_view, &self.capture_pipeline);
+
+        command_encoder.copy_texture_to_buffer(
+            capture_texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height)
+                }
+            },
+            extent
+        );
+
+        self.context.queue.submit(Some(command_encoder.finish()));
+
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            // Only fails if `read_pixels` already gave up on the result
+            let _ = sender.send(result);
+        });
+        self.context.device.poll(wgpu::Maintain::Wait);
+
+        receiver.recv()
+            .map_err(|_| Chip8Error::FrameCapture("the capture buffer was never mapped".to_string()))?
+            .map_err(|error| Chip8Error::FrameCapture(format!("couldn't map the capture buffer: {error}")))?;
+
+        let padded_frame = buffer_slice.get_mapped_range();
+        let mut frame = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
+
+        for row in padded_frame.chunks(padded_bytes_per_row as usize) {
+            frame.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+
+        drop(padded_frame);
+        readback_buffer.unmap();
+
+        Ok(frame)
+    }
+}
+/// `WgpuDisplay::gen_texture_data` for the benchmarks, which can't reach it otherwise. Not part of the API
+#[doc(hidden)]
+pub fn gen_texture_data(pixels: &Pixels, second_plane: &Pixels) -> [u8; Display::MAX_WIDTH * Display::MAX_HEIGHT] {
+    WgpuDisplay::gen_texture_data(pixels, second_plane)
+}
+
+impl DisplayBackend for WgpuDisplay {
+    fn render(&mut self, pixels: &Pixels) -> Result<bool, Chip8Error> {
+        WgpuDisplay::render(self, pixels).map_err(Chip8Error::Render)
+    }
+
+    fn set_resolution(&mut self, width: usize, height: usize) {
+        WgpuDisplay::set_resolution(self, width, height);
+    }
+
+    fn set_scanlines(&mut self, enabled: bool) {
+        WgpuDisplay::set_scanlines(self, enabled);
+    }
+
+    fn set_grid(&mut self, enabled: bool) {
+        WgpuDisplay::set_grid(self, enabled);
+    }
+
+    fn set_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) {
+        WgpuDisplay::set_colors(self, foreground, background);
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        WgpuDisplay::set_palette(self, palette);
+    }
+
+    fn set_second_plane(&mut self, pixels: &Pixels) {
+        WgpuDisplay::set_second_plane(self, pixels);
+    }
+
+    fn set_dimmed(&mut self, dimmed: bool) {
+        WgpuDisplay::set_dimmed(self, dimmed);
+    }
+
+    fn set_overlay(&mut self, text: Option<&str>) {
+        WgpuDisplay::set_overlay(self, text);
+    }
+
+    #[cfg(feature = "debug")]
+    fn upload_ghosts(&mut self, intensities: &[u8]) {
+        WgpuDisplay::upload_ghosts(self, intensities);
+    }
+
+    fn set_vsync(&mut self, vsync: bool) -> Option<PresentMode> {
+        let present_mode = match WgpuDisplay::set_vsync(self, vsync) {
+            wgpu::PresentMode::Mailbox => PresentMode::Mailbox,
+            wgpu::PresentMode::Immediate | wgpu::PresentMode::AutoNoVsync => PresentMode::Immediate,
+            _ => PresentMode::Vsync
+        };
+
+        Some(present_mode)
+    }
+
+    fn is_vsync(&self) -> bool {
+        WgpuDisplay::is_vsync(self)
+    }
+
+    fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
+        WgpuDisplay::resize(self, new_size);
+    }
+
+    fn texture_uploads(&self) -> u64 {
+        WgpuDisplay::texture_uploads(self)
+    }
+
+    fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        WgpuDisplay::size(self)
+    }
+
+    fn read_pixels(&mut self, pixels: &Pixels) -> Result<Vec<u8>, Chip8Error> {
+        WgpuDisplay::read_pixels(self, pixels)
+    }
+}
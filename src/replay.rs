@@ -0,0 +1,65 @@
+//! Recording key presses with the frame they happened on so a run can be replayed exactly, see
+//! `Chip8::start_recording` and `Chip8::play_input_log`. Replays only match if the RNG is seeded the same way
+
+use std::{fmt, str::FromStr};
+
+/// A CHIP-8 key being pressed or released
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputEvent {
+    /// How many 60Hz frames after the recording started it happened
+    pub frame: u64,
+    /// The key on the CHIP-8's keypad, 0x0 to 0xF
+    pub key: u8,
+    pub pressed: bool
+}
+
+/// Recorded input, in the order it happened.
+///
+/// As text every event is a line of `frame key down|up` with the key in hex, e.g. `120 A down`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputLog {
+    pub events: Vec<InputEvent>
+}
+
+impl fmt::Display for InputLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{} {:X} {}", event.frame, event.key, if event.pressed {"down"} else {"up"})?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for InputLog {
+    type Err = String;
+
+    /// Parses the text written by `to_string`. Blank lines are skipped
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut events = Vec::new();
+
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = |message: &str| format!("line {}: {message}", i + 1);
+
+            let [frame, key, state] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(error("expected a frame, a key and down or up"));
+            };
+
+            let frame = frame.parse().map_err(|_| error("invalid frame"))?;
+
+            let key = u8::from_str_radix(key, 16).ok()
+                .filter(|key| *key <= 0xF)
+                .ok_or_else(|| error("the key must be a hex digit"))?;
+
+            let pressed = match state {
+                "down" => true,
+                "up" => false,
+                _ => return Err(error("expected down or up"))
+            };
+
+            events.push(InputEvent { frame, key, pressed });
+        }
+
+        Ok(Self { events })
+    }
+}
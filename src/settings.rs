@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::quirks::Quirks;
+
+/// What a game was last run with, so it runs the same way the next time it's loaded
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub quirks: Quirks,
+    pub cycles_per_frame: u32,
+    pub foreground: [u8; 3],
+    pub background: [u8; 3]
+}
+
+/// The path of a file in the emulator's config directory, None if the OS has no config directory for the user
+pub(crate) fn config_file(name: &str) -> Option<PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", "chip8")?;
+    Some(dirs.config_dir().join(name))
+}
+
+/// Where the settings of every game are stored
+fn settings_path() -> Option<PathBuf> {
+    config_file("game_settings.json")
+}
+
+/// Every game's settings keyed by the hex `Chip8::rom_hash` of its ROM. Empty if the file couldn't be read
+fn load_all() -> BTreeMap<String, GameSettings> {
+    settings_path()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .unwrap_or_default()
+}
+
+fn key(rom_hash: u64) -> String {
+    format!("{rom_hash:016x}")
+}
+
+/// The settings saved for the ROM with this hash, if any
+pub fn load(rom_hash: u64) -> Option<GameSettings> {
+    load_all().remove(&key(rom_hash))
+}
+
+/// Saves the settings for the ROM with this hash, replacing what was saved before
+pub fn save(rom_hash: u64, settings: GameSettings) -> std::io::Result<()> {
+    let file = settings_path()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config directory"))?;
+
+    let mut all = load_all();
+    all.insert(key(rom_hash), settings);
+
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(file, serde_json::to_vec_pretty(&all)?)
+}
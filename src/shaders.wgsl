@@ -1,33 +1,87 @@
 struct VertexOutput {
   @builtin(position) pos: vec4<f32>,
-  @location(0) @interpolate(flat) on: f32,
+  @location(0) uv: vec2<f32>,
 };
 
-@vertex
-fn vs_main(@location(0) vpos: vec2<f32>, @location(1) ipos: vec2<f32>, @location(2) on: f32) -> VertexOutput {
-  // vpos is the vertex position, ipos is the instance position, on is whether or not this tile is illuminated
+@group(0) @binding(0)
+var screen: texture_2d<f32>;
+@group(0) @binding(1)
+var screen_sampler: sampler;
 
-  var output: VertexOutput;
+struct Effects {
+  scanlines: u32,
+  grid: u32,
+  overlay: u32,
+  dimmed: u32,
+  // Indexed by the screen texture, 0 is the background
+  palette: array<vec4<f32>, 4>,
+  // How much of the screen texture holds the display, lower resolutions only use its top left corner
+  width: u32,
+  height: u32,
+};
+
+@group(0) @binding(2)
+var<uniform> effects: Effects;
 
-  let world_width = 64.0;
-  let world_height = 32.0;
+// Debugging text drawn over the screen at twice its resolution
+@group(0) @binding(3)
+var overlay: texture_2d<f32>;
 
-  let world_x = vpos.x + ipos.x;
-  let world_y = vpos.y + ipos.y;
+// How strongly each pixel is tinted after a collision turned it off, laid out like the screen texture
+@group(0) @binding(4)
+var ghosts: texture_2d<f32>;
+
+@vertex
+fn vs_main(@location(0) vpos: vec2<f32>) -> VertexOutput {
+  // vpos is a corner of the unit quad, (0, 0) being the top left of the screen
+
+  var output: VertexOutput;
 
-  let x = world_x / world_width * 2.0 - 1.0;
-  let y = -(world_y / world_height * 2.0 - 1.0);
+  let x = vpos.x * 2.0 - 1.0;
+  let y = -(vpos.y * 2.0 - 1.0);
 
   output.pos = vec4<f32>(x, y, 0.0, 1.0);
-  output.on = on;
+  output.uv = vpos;
 
   return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-  // the colour will be on for each rgb value: if on is zero it should be black, and if on is 1 it should be white perfect
-  var c: f32 = f32(input.on);
+  // the screen texture holds each pixel's palette index divided by 3, 0.0 for pixels that are off in both planes
+  let resolution = vec2<f32>(f32(effects.width), f32(effects.height));
+  let screen_uv = input.uv * resolution / vec2<f32>(textureDimensions(screen));
+  let index = u32(round(textureSample(screen, screen_sampler, screen_uv).r * 3.0));
+  // How much of the pixel's color shows over the background
+  var c = select(0.0, 1.0, index != 0u);
+  let ghost = textureSample(ghosts, screen_sampler, screen_uv).r;
 
-  return vec4<f32>(c, c, c, 1.0);
-}
\ No newline at end of file
+  // 0.0 where the overlay is clear, around 0.5 for the backdrop behind the text and 1.0 for the text itself
+  let o = textureSample(overlay, screen_sampler, input.uv).r;
+
+  // Where this fragment lies within its CHIP-8 pixel, from (0, 0) at the top left to (1, 1) at the bottom right
+  let cell = fract(input.uv * resolution);
+
+  if (effects.scanlines != 0u && cell.y > 0.7) {
+    c *= 0.5;
+  }
+
+  if (effects.grid != 0u && (cell.x < 0.1 || cell.y < 0.1)) {
+    c *= 0.6;
+  }
+
+  var color = mix(effects.palette[0], effects.palette[index], c);
+  color = mix(color, vec4<f32>(1.0, 0.0, 0.0, 1.0), ghost);
+
+  if (effects.dimmed != 0u) {
+    color = vec4<f32>(color.rgb * 0.4, 1.0);
+  }
+
+  if (effects.overlay != 0u && o > 0.75) {
+    color = vec4<f32>(1.0, 0.8, 0.0, 1.0);
+  } else if (effects.overlay != 0u && o > 0.25) {
+    color = vec4<f32>(color.rgb * 0.25, 1.0);
+  }
+
+  return color;
+}
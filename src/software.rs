@@ -0,0 +1,188 @@
+//! Draws the display on the CPU with softbuffer, for machines where wgpu can't find an adapter (servers, remote
+//! desktops, broken drivers). Every frame is drawn from scratch, which the CHIP-8's few pixels keep cheap enough
+
+use std::num::NonZeroU32;
+use crate::{display::{self, Display, DisplayBackend, Palette, Pixels}, error::Chip8Error, overlay};
+
+/// Draws the display's pixels to a window without a GPU. Follows the same effects as `WgpuDisplay`'s shader except the
+/// collision tint of the debug feature
+pub struct SoftwareRenderer {
+    // Declared before the context so it's dropped first
+    surface: softbuffer::Surface,
+    _context: softbuffer::Context,
+    size: winit::dpi::PhysicalSize<u32>,
+    width: usize,
+    height: usize,
+    palette: Palette,
+    second_plane: Pixels,
+    scanlines: bool,
+    grid: bool,
+    dimmed: bool,
+    overlay: Option<Vec<u8>> // The rasterized overlay text, see the overlay module
+}
+
+impl SoftwareRenderer {
+    /// Draws to `window`, which has to outlive the renderer
+    pub fn new(window: &winit::window::Window) -> Result<Self, Chip8Error> {
+        let init_error = |error: softbuffer::SoftBufferError| Chip8Error::SoftwareRendererInit(error.to_string());
+
+        // SAFETY: the window outlives the renderer, which owns both the context and the surface
+        let context = unsafe { softbuffer::Context::new(window) }.map_err(init_error)?;
+        let surface = unsafe { softbuffer::Surface::new(&context, window) }.map_err(init_error)?;
+
+        let mut renderer = SoftwareRenderer {
+            surface,
+            _context: context,
+            size: winit::dpi::PhysicalSize::new(0, 0),
+            width: Display::WIDTH,
+            height: Display::HEIGHT,
+            palette: Display::DEFAULT_PALETTE,
+            second_plane: [0; Display::MAX_HEIGHT],
+            scanlines: false,
+            grid: false,
+            dimmed: false,
+            overlay: None
+        };
+
+        renderer.resize(&window.inner_size());
+
+        Ok(renderer)
+    }
+
+    /// Resizes the buffer drawn into to match the window. Nothing is drawn while either side is 0, e.g. when minimized
+    pub fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
+        self.size = *new_size;
+
+        if let (Some(width), Some(height)) = (NonZeroU32::new(new_size.width), NonZeroU32::new(new_size.height)) {
+            if let Err(error) = self.surface.resize(width, height) {
+                warn!("Couldn't resize the software renderer's buffer: {error}");
+            }
+        }
+    }
+
+    /// Draws the frame as 0RGB, one u32 per pixel of the window
+    fn draw(&self, pixels: &Pixels) -> Vec<u32> {
+        let window_width = self.size.width as usize;
+        let window_height = self.size.height as usize;
+
+        let mut frame = Vec::with_capacity(window_width * window_height);
+
+        for window_y in 0..window_height {
+            let y = window_y * self.height / window_height;
+            // Where this pixel lies within its CHIP-8 pixel, from 0.0 at the top to 1.0 at the bottom
+            let cell_y = (window_y * self.height % window_height) as f32 / window_height as f32;
+
+            for window_x in 0..window_width {
+                let x = window_x * self.width / window_width;
+                let cell_x = (window_x * self.width % window_width) as f32 / window_width as f32;
+
+                let index = display::color_index(pixels, &self.second_plane, x, y);
+                let mut c = if index != 0 {1.0} else {0.0};
+
+                if self.scanlines && cell_y > 0.7 {
+                    c *= 0.5;
+                }
+
+                if self.grid && (cell_x < 0.1 || cell_y < 0.1) {
+                    c *= 0.6;
+                }
+
+                let mut color = [0, 1, 2].map(|channel| {
+                    let background = self.palette[0][channel] as f32;
+                    background + (self.palette[index][channel] as f32 - background) * c
+                });
+
+                if self.dimmed {
+                    color = color.map(|channel| channel * 0.4);
+                }
+
+                if let Some(overlay) = &self.overlay {
+                    let texel = overlay[window_y * overlay::HEIGHT / window_height * overlay::WIDTH + window_x * overlay::WIDTH / window_width];
+
+                    if texel == overlay::TEXT {
+                        color = [255.0, 204.0, 0.0];
+                    } else if texel == overlay::BACKDROP {
+                        color = color.map(|channel| channel * 0.25);
+                    }
+                }
+
+                frame.push((color[0] as u32) << 16 | (color[1] as u32) << 8 | color[2] as u32);
+            }
+        }
+
+        frame
+    }
+}
+
+impl DisplayBackend for SoftwareRenderer {
+    /// A buffer that can't be presented is logged and the frame skipped, so it's drawn again on the next call
+    fn render(&mut self, pixels: &Pixels) -> Result<bool, Chip8Error> {
+        if self.size.width == 0 || self.size.height == 0 {
+            return Ok(false);
+        }
+
+        let frame = self.draw(pixels);
+
+        let presented = self.surface.buffer_mut().and_then(|mut buffer| {
+            buffer.copy_from_slice(&frame);
+            buffer.present()
+        });
+
+        match presented {
+            Ok(()) => Ok(true),
+            Err(error) => {
+                warn!("Couldn't present the frame: {error}");
+                Ok(false)
+            }
+        }
+    }
+
+    fn set_resolution(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn set_scanlines(&mut self, enabled: bool) {
+        self.scanlines = enabled;
+    }
+
+    fn set_grid(&mut self, enabled: bool) {
+        self.grid = enabled;
+    }
+
+    fn set_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) {
+        self.palette[1] = foreground;
+        self.palette[0] = background;
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    fn set_second_plane(&mut self, pixels: &Pixels) {
+        self.second_plane = *pixels;
+    }
+
+    fn set_dimmed(&mut self, dimmed: bool) {
+        self.dimmed = dimmed;
+    }
+
+    fn set_overlay(&mut self, text: Option<&str>) {
+        self.overlay = text.map(overlay::rasterize);
+    }
+
+    fn resize(&mut self, new_size: &winit::dpi::PhysicalSize<u32>) {
+        SoftwareRenderer::resize(self, new_size);
+    }
+
+    fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.size
+    }
+
+    fn read_pixels(&mut self, pixels: &Pixels) -> Result<Vec<u8>, Chip8Error> {
+        Ok(self.draw(pixels)
+            .into_iter()
+            .flat_map(|color| [(color >> 16) as u8, (color >> 8) as u8, color as u8, 0xFF])
+            .collect())
+    }
+}
@@ -0,0 +1,64 @@
+use chip8::{asm::{self, AsmError}, instruction::{Instruction, supported_opcodes}};
+
+/// Concrete opcodes for a pattern like `8XY4`, with its operands all zeros, all ones and a mix
+fn instances(pattern: &str) -> Vec<u16> {
+    ["000", "FFF", "3A5"].iter().map(|fill| {
+        let digits: String = pattern.chars().map(|digit| match digit {
+            'X' => fill.as_bytes()[0] as char,
+            'Y' => fill.as_bytes()[1] as char,
+            'N' => fill.as_bytes()[2] as char,
+            _ => digit
+        }).collect();
+
+        u16::from_str_radix(&digits, 16).unwrap()
+    }).collect()
+}
+
+#[test]
+fn every_supported_opcode_round_trips() {
+    for info in supported_opcodes() {
+        for opcode in instances(info.pattern) {
+            let source = asm::disassemble(&Instruction::new(opcode));
+
+            assert_eq!(asm::assemble(&source), Ok(opcode.to_be_bytes().to_vec()), "{} ({source})", info.pattern);
+        }
+    }
+}
+
+#[test]
+fn labels_resolve_to_their_address() {
+    let program = asm::assemble("
+        start: LD V0, 1 ; the first instruction is at 0x200
+        loop:
+            JP loop
+            JP start
+    ").unwrap();
+
+    assert_eq!(program, [0x60, 0x01, 0x12, 0x02, 0x12, 0x00]);
+}
+
+fn error(source: &str) -> AsmError {
+    asm::assemble(source).expect_err("the source assembled")
+}
+
+#[test]
+fn labels_defined_twice_are_rejected() {
+    let error = error("loop: CLS\nloop: RET");
+
+    assert_eq!(error.line, 2);
+    assert_eq!(error.message, "label \"loop\" defined twice");
+}
+
+#[test]
+fn values_out_of_range_are_rejected() {
+    assert_eq!(error("LD V0, 256").message, "0x100 doesn't fit in 0xFF");
+    assert_eq!(error("JP 0x1000").message, "0x1000 doesn't fit in 0xFFF");
+    assert_eq!(error("DRW V0, V1, 16").message, "0x10 doesn't fit in 0xF");
+    assert_eq!(error("DB 0x100").message, "0X100 doesn't fit in 0xFF");
+}
+
+#[test]
+fn unknown_mnemonics_and_operands_are_rejected() {
+    assert_eq!(error("CLS\nNOP").to_string(), "line 2: invalid instruction \"NOP\" with 0 operand(s)");
+    assert_eq!(error("JP nowhere").message, "unknown number or label \"NOWHERE\"");
+}
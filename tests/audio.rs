@@ -0,0 +1,27 @@
+#![cfg(feature = "audio")]
+
+use chip8::{Chip8, Chip8Config, display::NullDisplay};
+
+#[test]
+fn machines_without_an_audio_device_run_silently() {
+    let mut chip8 = Chip8::from_config_with_backend_and_audio(
+        Chip8Config::default(),
+        Box::new(NullDisplay),
+        || Err(rodio::StreamError::NoDevice)
+    ).unwrap();
+
+    assert!(chip8.audio_mixer().is_none());
+    assert!(chip8.is_muted());
+
+    // Sets the sound timer to 3 and halts
+    chip8.load_program(vec![0x60, 0x03, 0xF0, 0x18, 0x12, 0x04]).unwrap();
+    chip8.run_cycles(3).unwrap();
+    assert_eq!(chip8.sound_timer(), 3);
+
+    for _ in 0..3 {
+        chip8.tick_timers();
+    }
+
+    assert_eq!(chip8.sound_timer(), 0);
+    assert!(chip8.is_halted());
+}
@@ -0,0 +1,60 @@
+use chip8::{Chip8Config, Quirks, compat::{CompatReport, check_rom}};
+
+#[test]
+fn risky_instructions_are_reported() {
+    let program = vec![
+        0x61, 0x01, // V1 = 1
+        0x80, 0x16, // Shifts with X != Y
+        0x51, 0x21, // Not an instruction
+        0xAE, 0xA0, // Points I at the reserved memory
+        0xF0, 0x55, // and writes to it
+        0x12, 0x0A  // Halts
+    ];
+
+    let report = check_rom(program, Chip8Config::default(), 100).unwrap();
+
+    assert_eq!(report, CompatReport {
+        cycles_run: 6,
+        unknown_opcodes: vec![(0x204, 0x5121)],
+        reserved_memory_accesses: vec![0x208],
+        shift_quirk_uses: 1,
+        halted: true,
+        ..CompatReport::default()
+    });
+
+    assert_eq!(report.warnings(), [
+        "5121 at 0204 isn't a supported instruction and was skipped",
+        "The instruction at 0208 accesses memory the original interpreter reserved for itself",
+        "Shifted with X != Y 1 times, try toggling the shift_uses_vy quirk"
+    ]);
+}
+
+#[test]
+fn safe_programs_have_no_warnings() {
+    // Draws the 0 and halts
+    let report = check_rom(vec![0xD0, 0x05, 0x12, 0x02], Chip8Config::default(), 100).unwrap();
+
+    assert!(report.warnings().is_empty());
+    assert_eq!(report.max_draws_per_frame, 1);
+    assert!(report.halted);
+    assert!(report.to_string().contains("Nothing risky found"));
+}
+
+#[test]
+fn only_cycles_that_ran_are_counted() {
+    // Pauses on the unknown opcode with halt_on_error, nothing after it runs
+    let config = Chip8Config { halt_on_error: true, ..Chip8Config::default() };
+    let report = check_rom(vec![0x51, 0x21, 0x60, 0x01, 0x12, 0x04], config, 100).unwrap();
+
+    assert_eq!(report.cycles_run, 1);
+    assert_eq!(report.unknown_opcodes, [(0x200, 0x5121)]);
+    assert!(!report.halted);
+
+    // Every draw waits for the next frame with display_wait
+    let config = Chip8Config { quirks: Quirks { display_wait: true, ..Quirks::default() }, ..Chip8Config::default() };
+    let report = check_rom(vec![0xD0, 0x05, 0xD0, 0x05, 0xD0, 0x05, 0x12, 0x06], config, 100).unwrap();
+
+    assert_eq!(report.cycles_run, 4);
+    assert_eq!(report.max_draws_per_frame, 1);
+    assert!(report.halted);
+}
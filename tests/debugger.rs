@@ -0,0 +1,220 @@
+use chip8::{Chip8, Chip8Config, Chip8Error, Quirks, chip8::{DrawInfo, FONT_BASE, FONT_LEN, MAX_PROGRAM_SIZE, MemoryRegionKind, PROGRAM_START, RAM_SIZE, StepOutcome}};
+
+/// Calls the subroutine at 0x206, which sets V2, and then halts with V1 set
+const CALLS_A_SUBROUTINE: [u8; 10] = [
+    0x22, 0x06, // 200: CALL 0x206
+    0x61, 0x01, // 202: LD V1, 0x01
+    0x12, 0x04, // 204: JP 0x204
+    0x62, 0x02, // 206: LD V2, 0x02
+    0x00, 0xEE  // 208: RET
+];
+
+#[test]
+fn step_out_runs_until_the_subroutine_returns() {
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(CALLS_A_SUBROUTINE.to_vec()).unwrap();
+    chip8.step().unwrap();
+
+    chip8.step_out().unwrap();
+
+    assert_eq!(chip8.pc(), 0x202);
+    assert!(chip8.stack().is_empty());
+    assert!(chip8.paused);
+    assert_eq!(chip8.registers()[..3], [0, 0, 2]);
+}
+
+#[test]
+fn step_out_gives_up_on_subroutines_that_never_return() {
+    let mut chip8 = Chip8::headless();
+    // The subroutine at 0x204 loops forever without jumping to itself
+    chip8.load_program(vec![0x22, 0x04, 0x00, 0x00, 0x70, 0x01, 0x12, 0x04]).unwrap();
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.step_out(), Err(Chip8Error::CycleLimitReached(Chip8::STEP_OUT_LIMIT)));
+}
+
+#[test]
+fn run_until_stops_at_the_address() {
+    let mut chip8 = Chip8::headless();
+    // Counts V0 up in a loop, 0x206 is only reached once the loop is done
+    chip8.load_program(vec![0x70, 0x01, 0x30, 0x05, 0x12, 0x00, 0x12, 0x06]).unwrap();
+
+    assert_eq!(chip8.run_until(0x206, 100), Ok(StepOutcome::Reached));
+    assert_eq!(chip8.pc(), 0x206);
+    assert_eq!(chip8.registers()[0], 5);
+    assert!(chip8.paused);
+
+    assert_eq!(chip8.run_until(0x200, 100), Ok(StepOutcome::Halted));
+}
+
+#[test]
+fn run_until_gives_up_after_max_cycles() {
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(vec![0x70, 0x01, 0x12, 0x00]).unwrap();
+
+    assert_eq!(chip8.run_until(0x300, 10), Ok(StepOutcome::CycleLimitReached));
+    assert_eq!(chip8.registers()[0], 5);
+}
+
+#[test]
+fn run_until_draw_waits_for_the_next_draw_with_display_wait() {
+    let mut chip8 = Chip8::test_instance();
+    chip8.set_quirks(Quirks { display_wait: true, ..Quirks::default() });
+    chip8.load_program(vec![
+        0xD0, 0x05, // 200: DRW V0, V0, 5
+        0x61, 0x01, // 202: LD V1, 0x01
+        0xD0, 0x05, // 204: DRW V0, V0, 5
+        0x12, 0x06  // 206: JP 0x206
+    ]).unwrap();
+
+    assert_eq!(chip8.run_until_draw(100), Ok(true));
+    assert_eq!((chip8.pc(), chip8.registers()[1]), (0x202, 0));
+
+    // The first draw is still waiting for the vertical blank, that doesn't count as drawing again
+    assert_eq!(chip8.run_until_draw(100), Ok(true));
+    assert_eq!((chip8.pc(), chip8.registers()[1]), (0x206, 1));
+
+    assert_eq!(chip8.run_until_draw(100), Ok(false));
+    assert!(chip8.is_halted());
+}
+
+#[test]
+fn stack_follows_nested_calls() {
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(vec![
+        0x22, 0x04, // 200: CALL 0x204
+        0x12, 0x02, // 202: JP 0x202
+        0x22, 0x08, // 204: CALL 0x208
+        0x00, 0xEE, // 206: RET
+        0x00, 0xEE  // 208: RET
+    ]).unwrap();
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_stack(), [0x202]);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_stack(), [0x202, 0x206]);
+    assert_eq!(chip8.call_stack().len(), 2);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_stack(), [0x202]);
+
+    chip8.step().unwrap();
+    assert!(chip8.call_stack().is_empty());
+    assert_eq!(chip8.stack(), chip8.call_stack());
+    assert_eq!(chip8.pc(), 0x202);
+}
+
+#[test]
+fn last_draw_info_describes_the_last_sprite() {
+    let mut chip8 = Chip8::headless();
+    // Draws the font's 7 at (70, 3), which wraps around to (6, 3)
+    chip8.load_program(vec![0x60, 0x07, 0xF0, 0x29, 0x61, 0x46, 0x62, 0x03, 0xD1, 0x25]).unwrap();
+
+    chip8.set_record_draws(true);
+
+    chip8.run_cycles(4).unwrap();
+    assert_eq!(chip8.last_draw_info(), None);
+
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.last_draw_info(), Some(DrawInfo {
+        source_start: (FONT_BASE + 7 * 5) as u16,
+        source_len: 5,
+        x: 6,
+        y: 3,
+        width: 8,
+        height: 5
+    }));
+}
+
+#[test]
+fn start_paused_runs_nothing_until_unpaused() {
+    let mut chip8 = Chip8::headless_from_config(Chip8Config { start_paused: true, ..Chip8Config::default() }).unwrap();
+    chip8.load_program(CALLS_A_SUBROUTINE.to_vec()).unwrap();
+
+    assert!(chip8.paused);
+
+    chip8.cycle().unwrap();
+    assert_eq!(chip8.pc(), 0x200);
+
+    chip8.paused = false;
+    chip8.cycle().unwrap();
+    assert_eq!(chip8.pc(), 0x206);
+
+    // Reloading starts paused again
+    chip8.reset_and_reload(CALLS_A_SUBROUTINE.to_vec()).unwrap();
+    assert!(chip8.paused);
+}
+
+#[test]
+fn traces_are_the_same_on_every_run() {
+    let program = vec![
+        0x60, 0x05, // 200: LD V0, 0x05
+        0xA3, 0x00, // 202: LD I, 0x300
+        0xC1, 0xFF, // 204: RND V1, 0xFF
+        0x70, 0x01, // 206: ADD V0, 0x01
+        0x12, 0x08  // 208: JP 0x208
+    ];
+
+    let trace = Chip8::run_trace(program.clone(), 100).unwrap();
+
+    assert_eq!(trace, Chip8::run_trace(program, 100).unwrap());
+    // Stops once the program halts
+    assert_eq!(trace.iter().map(|entry| entry.pc).collect::<Vec<_>>(), [0x200, 0x202, 0x204, 0x206, 0x208]);
+
+    let last = trace[4];
+    assert_eq!((last.opcode, last.registers[0], last.i_register), (0x1208, 6, 0x300));
+    assert_eq!(trace[0].to_string(), "0200: 6005 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I:0000");
+}
+
+#[test]
+fn the_memory_map_covers_memory_in_order() {
+    const { assert!(FONT_BASE + FONT_LEN <= PROGRAM_START) };
+    const { assert!(PROGRAM_START < RAM_SIZE) };
+    assert_eq!(MAX_PROGRAM_SIZE, RAM_SIZE - PROGRAM_START);
+
+    for font_base in [0, FONT_BASE, PROGRAM_START - FONT_LEN] {
+        let chip8 = Chip8::headless_from_config(Chip8Config { font_base, ..Chip8Config::default() }).unwrap();
+        let map = chip8.memory_map();
+
+        assert_eq!(map.first().unwrap().range.start, 0);
+        assert_eq!(map.last().unwrap().range.end, RAM_SIZE);
+        assert!(map.windows(2).all(|pair| pair[0].range.end == pair[1].range.start), "{map:?}");
+
+        let font = map.iter().find(|region| region.kind == MemoryRegionKind::Font).unwrap();
+        assert_eq!(font.range, font_base..font_base + FONT_LEN);
+
+        let program = map.iter().find(|region| region.kind == MemoryRegionKind::Program).unwrap();
+        assert_eq!(program.range.len(), MAX_PROGRAM_SIZE);
+    }
+}
+
+#[test]
+fn hexdump_shows_the_font() {
+    let chip8 = Chip8::headless();
+    let dump = chip8.hexdump(FONT_BASE as u16, 16);
+
+    assert_eq!(dump, "-- Font --\n0050: F0 90 90 90 F0 20 60 20 20 70 F0 10 F0 80 F0 F0  ..... `  p......\n");
+}
+
+#[test]
+fn hexdump_stops_at_the_end_of_memory() {
+    let chip8 = Chip8::headless();
+
+    assert_eq!(chip8.hexdump(0xFF8, 100), "-- Program --\n0FF8: 00 00 00 00 00 00 00 00                          ........\n");
+    assert_eq!(chip8.hexdump(0xFFF, usize::MAX).lines().count(), 2);
+    assert_eq!(chip8.hexdump(0x1000, 16), "");
+}
+
+#[test]
+fn the_current_instruction_is_the_last_one_fetched() {
+    let mut chip8 = Chip8::test_instance();
+    assert_eq!(chip8.current_opcode(), 0x0000);
+
+    chip8.load_program(vec![0x63, 0x2A, 0x00, 0xE0]).unwrap();
+    chip8.run_cycles(1).unwrap();
+
+    assert_eq!(chip8.current_opcode(), 0x632A);
+    assert_eq!(chip8.current_disassembly(), "LD V3, 0x2A");
+}
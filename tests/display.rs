@@ -0,0 +1,310 @@
+use chip8::{Chip8, Chip8Config, Chip8Error, display::{self, Display, Pixels}};
+
+/// Pixel (x, y) turned on in an otherwise blank plane
+fn plane_with(x: usize, y: usize) -> Pixels {
+    let mut plane = [0; Display::MAX_HEIGHT];
+    plane[y] = 1 << (Display::MAX_WIDTH - 1 - x);
+
+    plane
+}
+
+#[test]
+fn color_index_combines_both_planes() {
+    let blank = [0; Display::MAX_HEIGHT];
+    let first = plane_with(3, 2);
+    let second = plane_with(3, 2);
+
+    assert_eq!(display::color_index(&blank, &blank, 3, 2), 0);
+    assert_eq!(display::color_index(&first, &blank, 3, 2), 1);
+    assert_eq!(display::color_index(&blank, &second, 3, 2), 2);
+    assert_eq!(display::color_index(&first, &second, 3, 2), 3);
+
+    // Neighbouring pixels aren't affected
+    assert_eq!(display::color_index(&first, &second, 4, 2), 0);
+    assert_eq!(display::color_index(&first, &second, 3, 3), 0);
+}
+
+#[test]
+fn only_the_first_plane_shows_two_colors() {
+    let mut display = Display::headless();
+    display.set_pixel(0, 0, true);
+
+    assert_eq!(display.color_index(0, 0), 1);
+    assert_eq!(display.color_index(1, 0), 0);
+
+    display.set_second_plane(&plane_with(1, 0));
+    assert_eq!(display.color_index(1, 0), 2);
+
+    display.set_second_plane(&plane_with(0, 0));
+    assert_eq!(display.color_index(0, 0), 3);
+
+    display.clear_screen();
+    assert_eq!(display.color_index(0, 0), 0);
+}
+
+/// Turns on every pixel where (x + 2y) % 5 == 0, which doesn't line up with the bytes pixels are packed in
+fn draw_pattern(display: &mut Display) {
+    for y in 0..display.height() {
+        for x in 0..display.width() {
+            display.set_pixel(x, y, (x + 2 * y) % 5 == 0);
+        }
+    }
+}
+
+/// XORs a sprite a pixel at a time with `get_pixel`/`set_pixel`, what `Display::draw` does a row at a time
+fn draw_per_pixel(display: &mut Display, x: u8, y: u8, sprite: &[u8], (wrap_x, wrap_y): (bool, bool)) -> bool {
+    let (width, height) = (display.width(), display.height());
+    let (x, y) = (x as usize % width, y as usize % height);
+    let mut collision = false;
+
+    for (row, byte) in sprite.iter().enumerate() {
+        let py = match y + row {
+            py if py < height => py,
+            py if wrap_y => py % height,
+            _ => break
+        };
+
+        for column in (0..8).filter(|column| byte >> (7 - column) & 1 == 1) {
+            let px = match x + column {
+                px if px < width => px,
+                px if wrap_x => px % width,
+                _ => continue
+            };
+
+            let on = display.get_pixel(px, py);
+            collision |= on;
+            display.set_pixel(px, py, !on);
+        }
+    }
+
+    collision
+}
+
+#[test]
+fn drawing_a_row_at_a_time_matches_drawing_per_pixel() {
+    let sprites: [(u8, u8, &[u8]); 6] = [
+        (3, 4, &[0xF0, 0x90, 0xF0, 0x90, 0xF0]),
+        (60, 5, &[0xFF, 0x81, 0xFF]), // Crosses the right edge
+        (10, 29, &[0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55]), // Crosses the bottom edge
+        (61, 30, &[0xFF; 8]), // Crosses both
+        (200, 100, &[0x3C, 0x42, 0x81]), // Starts past the edges
+        (3, 4, &[0xF0, 0x90, 0xF0, 0x90, 0xF0]) // Collides with the first one
+    ];
+
+    for wrapping in [(true, true), (false, false), (true, false), (false, true)] {
+        let mut display = Display::headless();
+        let mut expected = Display::headless();
+        display.set_wrapping(wrapping.0, wrapping.1);
+        draw_pattern(&mut display);
+        draw_pattern(&mut expected);
+
+        for &(x, y, sprite) in &sprites {
+            let collision = draw_per_pixel(&mut expected, x, y, sprite, wrapping);
+
+            assert_eq!(display.draw(x, y, sprite), collision, "VF of the sprite at ({x}, {y}) with {wrapping:?}");
+            assert_eq!(display.to_ascii(), expected.to_ascii(), "the sprite at ({x}, {y}) with {wrapping:?}");
+        }
+    }
+}
+
+#[test]
+fn packed_bits_round_trip_in_both_resolutions() {
+    for (width, height) in [(64, 32), (128, 64)] {
+        let mut display = Display::headless();
+        display.set_resolution(width, height);
+        draw_pattern(&mut display);
+
+        let bits = display.to_packed_bits();
+        assert_eq!(bits.len(), width * height / 8);
+        // Pixels 0 and 5 of the first row
+        assert_eq!(bits[0], 0b1000_0100);
+
+        let mut copy = Display::headless();
+        copy.set_packed_bits(&bits).unwrap();
+
+        assert_eq!((copy.width(), copy.height()), (width, height));
+        assert_eq!(copy.to_ascii(), display.to_ascii());
+    }
+}
+
+#[test]
+fn packed_bits_of_the_wrong_size_are_rejected() {
+    let mut display = Display::headless();
+    display.set_pixel(0, 0, true);
+
+    assert_eq!(display.set_packed_bits(&[0; 100]), Err(Chip8Error::PackedDisplaySize(100)));
+    assert!(display.get_pixel(0, 0));
+}
+
+#[test]
+fn failing_instructions_show_the_crash_screen_until_resumed() {
+    for crash_screen in [false, true] {
+        let mut chip8 = Chip8::headless_from_config(Chip8Config { halt_on_error: true, crash_screen, ..Chip8Config::default() }).unwrap();
+        // Returns without a call after setting V0
+        chip8.load_program(vec![0x60, 0x00, 0x00, 0xEE]).unwrap();
+
+        assert_eq!(chip8.run_cycles(2), Err(Chip8Error::StackUnderflow));
+        assert!(chip8.paused && chip8.display().is_dimmed());
+
+        if crash_screen {
+            assert_eq!((chip8.crash_error(), chip8.crash_address()), (Some(&Chip8Error::StackUnderflow), Some(0x202)));
+            // The failing instruction's address rather than the pc, which already moved past it
+            assert_eq!(chip8.display().overlay(), Some("CRASHED\nPC:0202\nOP:00EE"));
+        } else {
+            assert_eq!((chip8.crash_error(), chip8.crash_address(), chip8.display().overlay()), (None, None, None));
+        }
+
+        chip8.paused = false;
+        chip8.run_cycles(0).unwrap();
+
+        assert_eq!((chip8.crash_error(), chip8.display().overlay()), (None, None));
+        assert!(!chip8.display().is_dimmed());
+    }
+}
+
+#[test]
+fn drawing_dirties_the_display_until_it_renders() {
+    let mut display = Display::headless();
+    assert!(!display.is_dirty());
+
+    display.draw(0, 0, &[0x80]);
+    assert!(display.is_dirty());
+
+    display.render().unwrap();
+    assert!(!display.is_dirty());
+
+    // Setting a pixel to what it already is changes nothing
+    display.set_pixel(0, 0, true);
+    assert!(!display.is_dirty());
+
+    // Drawing the same sprite again erases it
+    display.draw(0, 0, &[0x80]);
+    assert!(display.is_dirty());
+    assert!(!display.get_pixel(0, 0));
+}
+
+#[test]
+fn the_host_can_set_every_pixel() {
+    let mut checkerboard = [[false; Display::WIDTH]; Display::HEIGHT];
+
+    for (y, row) in checkerboard.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = (x + y) % 2 == 0;
+        }
+    }
+
+    let mut chip8 = Chip8::headless();
+    // Switches to hires first, setting the framebuffer goes back to the default resolution
+    chip8.load_program(vec![0x00, 0xFF]).unwrap();
+    chip8.run_cycles(1).unwrap();
+    assert_eq!(chip8.display().width(), Display::MAX_WIDTH);
+
+    chip8.set_display(&checkerboard);
+    let display = chip8.display();
+
+    assert_eq!((display.width(), display.height()), (Display::WIDTH, Display::HEIGHT));
+    assert_eq!(display.diff(&checkerboard), 0);
+
+    for y in 0..Display::HEIGHT {
+        for x in 0..Display::WIDTH {
+            assert_eq!(display.get_pixel(x, y), (x + y) % 2 == 0, "pixel ({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn diff_counts_the_pixels_that_differ() {
+    let mut framebuffer = [[false; Display::WIDTH]; Display::HEIGHT];
+    framebuffer[0][0] = true;
+    framebuffer[31][63] = true;
+
+    let mut display = Display::headless();
+    display.set_pixel(0, 0, true);
+    display.set_pixel(63, 31, true);
+    assert_eq!(display.diff(&framebuffer), 0);
+
+    display.set_pixel(10, 5, true);
+    assert_eq!(display.diff(&framebuffer), 1);
+
+    framebuffer[5][10] = true;
+    display.set_pixel(63, 31, false);
+    assert_eq!(display.diff(&framebuffer), 1);
+}
+
+#[test]
+fn pixels_outside_the_default_resolution_count_as_differing() {
+    let mut framebuffer = [[false; Display::WIDTH]; Display::HEIGHT];
+    framebuffer[31][63] = true;
+
+    let mut display = Display::headless();
+    display.set_resolution(Display::MAX_WIDTH, Display::MAX_HEIGHT);
+    display.set_pixel(63, 31, true);
+    assert_eq!(display.diff(&framebuffer), 0);
+
+    // Past the right edge, the bottom edge and both
+    display.set_pixel(64, 0, true);
+    display.set_pixel(0, 32, true);
+    display.set_pixel(127, 63, true);
+    assert_eq!(display.diff(&framebuffer), 3);
+}
+
+#[test]
+fn redraws_are_needed_only_after_the_screen_changes() {
+    let mut chip8 = Chip8::headless();
+    // Draws the 0 sprite, sets a register and draws it again over itself
+    chip8.load_program(vec![0xD0, 0x05, 0x61, 0x01, 0xD0, 0x05]).unwrap();
+    assert!(!chip8.needs_redraw());
+
+    chip8.run_cycles(1).unwrap();
+    assert!(chip8.needs_redraw());
+
+    chip8.render().unwrap();
+    assert!(!chip8.needs_redraw());
+
+    chip8.run_cycles(1).unwrap();
+    assert!(!chip8.needs_redraw(), "only the screen changing needs a redraw");
+
+    chip8.run_cycles(1).unwrap();
+    assert!(chip8.needs_redraw());
+
+    chip8.render().unwrap();
+    assert!(!chip8.needs_redraw());
+}
+
+#[test]
+fn sprites_wrap_at_the_current_resolution() {
+    for (width, height) in [(64, 32), (128, 64)] {
+        let mut display = Display::headless();
+        display.set_resolution(width, height);
+
+        // 8x3 sprite 4 columns from the right edge and 2 rows from the bottom one
+        display.draw((width - 4) as u8, (height - 2) as u8, &[0xFF; 3]);
+
+        for (x, y) in [(width - 4, height - 2), (width - 1, height - 1), (0, height - 2), (3, height - 1), (0, 0), (3, 0)] {
+            assert!(display.get_pixel(x, y), "({x}, {y}) at {width}x{height}");
+        }
+
+        for (x, y) in [(4, 0), (width - 5, 0), (0, 1)] {
+            assert!(!display.get_pixel(x, y), "({x}, {y}) at {width}x{height}");
+        }
+
+        let lit: u32 = display.to_packed_bits().iter().map(|byte| byte.count_ones()).sum();
+        assert_eq!(lit, 8 * 3);
+
+        // Clipped, only the part inside the screen is drawn
+        display.clear_screen();
+        display.set_wrapping(false, false);
+        display.draw((width - 4) as u8, (height - 2) as u8, &[0xFF; 3]);
+
+        let lit: u32 = display.to_packed_bits().iter().map(|byte| byte.count_ones()).sum();
+        assert_eq!(lit, 4 * 2, "at {width}x{height}");
+    }
+
+    // A sprite that would wrap at 64 columns doesn't in hires
+    let mut display = Display::headless();
+    display.set_resolution(128, 64);
+    display.draw(62, 40, &[0xFF]);
+
+    assert!(display.get_pixel(64, 40) && display.get_pixel(69, 40));
+    assert!(!display.get_pixel(0, 40));
+}
@@ -0,0 +1,27 @@
+use chip8::Chip8Error;
+
+#[test]
+fn every_error_describes_itself() {
+    let cases = [
+        (Chip8Error::RomTooLarge, "the ROM doesn't fit in the memory after 0x200".to_string()),
+        (Chip8Error::StackOverflow, "called a subroutine with the stack full".to_string()),
+        (Chip8Error::StackUnderflow, "returned without being in a subroutine".to_string()),
+        (Chip8Error::OutOfBoundsMemory(0x1000), "accessed 1000, past the end of memory".to_string()),
+        (Chip8Error::UnknownOpcode(0x5121), "unknown opcode 5121".to_string()),
+        (Chip8Error::AudioInit("no device".to_string()), "couldn't initialize audio: no device".to_string()),
+        (Chip8Error::GpuInit("no adapter".to_string()), "couldn't initialize the GPU: no adapter".to_string()),
+        (Chip8Error::SoftwareRendererInit("no window".to_string()), "couldn't initialize the software renderer: no window".to_string()),
+        (Chip8Error::GamepadInit("unsupported".to_string()), "couldn't initialize gamepad support: unsupported".to_string()),
+        (Chip8Error::Render(wgpu::SurfaceError::Lost), format!("couldn't render: {}", wgpu::SurfaceError::Lost)),
+        (Chip8Error::FrameCapture("the window is 0x0".to_string()), "couldn't capture the frame: the window is 0x0".to_string()),
+        (Chip8Error::PackedDisplaySize(100), "100 bytes of packed pixels don't match any resolution".to_string()),
+        (Chip8Error::GifRecording("disk full".to_string()), "couldn't record the GIF: disk full".to_string()),
+        (Chip8Error::CycleLimitReached(1000), "gave up after 1000 cycles".to_string()),
+        (Chip8Error::FontOutOfRange(0x1C0), "the font at 01C0 doesn't fit below the program".to_string()),
+        (Chip8Error::RamTooSmall(0x200), "512 bytes of memory leave no room for a program after 0x200".to_string())
+    ];
+
+    for (error, message) in cases {
+        assert_eq!(error.to_string(), message);
+    }
+}
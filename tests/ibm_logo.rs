@@ -0,0 +1,123 @@
+use chip8::Chip8;
+
+/// The classic IBM logo ROM, it draws the logo and then jumps to itself forever
+const IBM_LOGO: [u8; 132] = [
+    0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C, 0x61, 0x08, 0xD0, 0x1F, 0x70, 0x09,
+    0xA2, 0x39, 0xD0, 0x1F, 0xA2, 0x48, 0x70, 0x08, 0xD0, 0x1F, 0x70, 0x04,
+    0xA2, 0x57, 0xD0, 0x1F, 0x70, 0x08, 0xA2, 0x66, 0xD0, 0x1F, 0x70, 0x08,
+    0xA2, 0x75, 0xD0, 0x1F, 0x12, 0x28, 0xFF, 0x00, 0xFF, 0x00, 0x3C, 0x00,
+    0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0xFF, 0x00, 0xFF, 0xFF, 0x00, 0xFF,
+    0x00, 0x38, 0x00, 0x3F, 0x00, 0x3F, 0x00, 0x38, 0x00, 0xFF, 0x00, 0xFF,
+    0x80, 0x00, 0xE0, 0x00, 0xE0, 0x00, 0x80, 0x00, 0x80, 0x00, 0xE0, 0x00,
+    0xE0, 0x00, 0x80, 0xF8, 0x00, 0xFC, 0x00, 0x3E, 0x00, 0x3F, 0x00, 0x3B,
+    0x00, 0x39, 0x00, 0xF8, 0x00, 0xF8, 0x03, 0x00, 0x07, 0x00, 0x0F, 0x00,
+    0xBF, 0x00, 0xFB, 0x00, 0xF3, 0x00, 0xE3, 0x00, 0x43, 0xE0, 0x00, 0xE0,
+    0x00, 0x80, 0x00, 0x80, 0x00, 0x80, 0x00, 0x80, 0x00, 0xE0, 0x00, 0xE0
+];
+
+const EXPECTED: &str = "\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+............########.#########...#####.........#####............\n\
+................................................................\n\
+............########.###########.######.......######............\n\
+................................................................\n\
+..............####.....###...###...#####.....#####..............\n\
+................................................................\n\
+..............####.....#######.....#######.#######..............\n\
+................................................................\n\
+..............####.....#######.....###.#######.###..............\n\
+................................................................\n\
+..............####.....###...###...###..#####..###..............\n\
+................................................................\n\
+............########.###########.#####...###...#####............\n\
+................................................................\n\
+............########.#########...#####....#....#####............\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n\
+................................................................\n";
+
+#[test]
+fn ibm_logo_draws_the_logo() {
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(IBM_LOGO.to_vec()).unwrap();
+
+    // The ROM draws everything in its first 20 instructions and then halts on its final self-jump
+    chip8.run_cycles(1000).unwrap();
+
+    let screen = chip8.display().to_ascii();
+
+    assert!(screen.contains('#'), "nothing was drawn");
+    assert_eq!(screen, EXPECTED, "expected\n{EXPECTED}\ngot\n{screen}");
+}
+
+#[test]
+fn run_until_draw_stops_after_each_sprite() {
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(IBM_LOGO.to_vec()).unwrap();
+
+    // Clearing the screen and setting up the first sprite come before it
+    assert_eq!(chip8.run_until_draw(1000), Ok(true));
+    assert_eq!(chip8.pc(), 0x20A);
+    assert!(chip8.display().to_ascii().contains('#'));
+
+    // The logo is made of 6 sprites, after those the ROM halts without drawing again
+    let mut draws = 1;
+
+    while chip8.run_until_draw(1000).unwrap() {
+        draws += 1;
+    }
+
+    assert_eq!(draws, 6);
+    assert!(chip8.is_halted());
+    assert_eq!(chip8.display().to_ascii(), EXPECTED);
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn gif_recording_writes_a_frame_per_sprite() {
+    let path = std::env::temp_dir().join("chip8-ibm-logo.gif");
+
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(IBM_LOGO.to_vec()).unwrap();
+    chip8.start_gif_recording(&path).unwrap();
+    assert!(chip8.is_recording_gif());
+
+    // A sprite per frame, then the logo sits still
+    while chip8.run_until_draw(1000).unwrap() {
+        chip8.tick_timers();
+    }
+
+    for _ in 0..30 {
+        chip8.tick_timers();
+    }
+
+    chip8.stop_gif_recording().unwrap();
+    assert!(!chip8.is_recording_gif());
+
+    let gif = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+
+    let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+    let mut frames = 0;
+
+    while decoder.read_next_frame().unwrap().is_some() {
+        frames += 1;
+    }
+
+    // The blank screen the recording started on and one for each of the 6 sprites, the still ones are merged
+    assert_eq!(frames, 7);
+}
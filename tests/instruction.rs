@@ -0,0 +1,27 @@
+use chip8::instruction::Instruction;
+
+#[test]
+fn operands_are_decoded_from_their_nibbles() {
+    let instruction = Instruction::new(0x8AB4);
+    assert_eq!((instruction.x(), instruction.y(), instruction.n()), (0xA, 0xB, 4));
+
+    assert_eq!(Instruction::new(0x6A2F).nn(), 0x2F);
+    assert_eq!(Instruction::new(0x6A2F).x(), 0xA);
+    assert_eq!(Instruction::new(0x1234).nnn(), 0x234);
+    assert_eq!(Instruction::from_bytes(0x12, 0x34), Instruction::new(0x1234));
+}
+
+#[test]
+fn xy_returns_both_register_indices() {
+    assert_eq!(Instruction::new(0x8AB4).xy(), (0xA, 0xB));
+    assert_eq!(Instruction::new(0x5120).xy(), (0x1, 0x2));
+    assert_eq!(Instruction::new(0xDF05).xy(), (0xF, 0x0));
+
+    // Indices into the registers, matching x and y
+    let instruction = Instruction::new(0x8FE0);
+    let (x, y): (usize, usize) = instruction.xy();
+    assert_eq!((x, y), (instruction.x(), instruction.y()));
+
+    let instruction = Instruction::new(0xD12F);
+    assert_eq!((instruction.nn(), instruction.nnn()), (0x2F, 0x12F));
+}
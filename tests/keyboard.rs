@@ -0,0 +1,59 @@
+use chip8::{Chip8, keyboard::Keyboard};
+use winit::event::VirtualKeyCode;
+
+#[test]
+fn clearing_releases_every_key() {
+    let mut keyboard = Keyboard::new();
+    keyboard.on_key_down(&VirtualKeyCode::Q);
+    keyboard.on_key_down(&VirtualKeyCode::W);
+    assert!(keyboard.is_pressed(0x4) && keyboard.is_pressed(0x5));
+
+    keyboard.clear();
+    assert!(!keyboard.is_pressed(0x4) && !keyboard.is_pressed(0x5));
+}
+
+#[test]
+fn clearing_keys_doesnt_complete_a_wait_for_a_key() {
+    let mut chip8 = Chip8::test_instance();
+    chip8.load_program(vec![0xF0, 0x0A]).unwrap();
+    chip8.run_cycles(1).unwrap();
+
+    chip8.on_key_down(&VirtualKeyCode::Key1);
+    assert!(chip8.is_key_pressed(0x1));
+
+    chip8.clear_keys();
+    assert!(!chip8.is_key_pressed(0x1));
+    assert!(chip8.is_waiting_for_key());
+}
+
+#[test]
+fn repeated_key_downs_arent_new_presses() {
+    let mut keyboard = Keyboard::new();
+    assert!(keyboard.is_key_event_new(&VirtualKeyCode::Q));
+
+    keyboard.on_key_down(&VirtualKeyCode::Q);
+    // The OS repeats the key down while it's held
+    assert!(!keyboard.is_key_event_new(&VirtualKeyCode::Q));
+    assert!(keyboard.is_key_event_new(&VirtualKeyCode::W));
+    assert!(keyboard.is_key_event_new(&VirtualKeyCode::Space), "keys that aren't mapped are never held");
+
+    keyboard.on_key_up(&VirtualKeyCode::Q);
+    assert!(keyboard.is_key_event_new(&VirtualKeyCode::Q));
+}
+
+#[test]
+fn repeated_key_downs_dont_resume_a_halt() {
+    let mut chip8 = Chip8::test_instance();
+    // Halts right away
+    chip8.load_program(vec![0x12, 0x00]).unwrap();
+    chip8.run_cycles(1).unwrap();
+
+    chip8.on_key_down(&VirtualKeyCode::Q);
+    assert!(!chip8.is_halted());
+
+    chip8.run_cycles(1).unwrap();
+    assert!(chip8.is_halted());
+
+    chip8.on_key_down(&VirtualKeyCode::Q);
+    assert!(chip8.is_halted());
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "net")]
+
+use chip8::{Chip8, net::{InputClient, InputServer, SpectatorClient, SpectatorServer}};
+use std::{io::Write, net::TcpStream};
+
+#[test]
+fn spectators_receive_the_screen() {
+    let mut server = SpectatorServer::bind("127.0.0.1:0").unwrap();
+    let mut client = SpectatorClient::connect(server.local_addr().unwrap()).unwrap();
+
+    // Draws the font's 0 in the top left corner
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(vec![0xF0, 0x29, 0xD0, 0x05]).unwrap();
+    chip8.run_cycles(2).unwrap();
+
+    // The connection may take a moment to show up on the server's side
+    while server.spectator_count() == 0 {
+        server.broadcast(&chip8);
+    }
+
+    let display = client.read_frame().unwrap();
+
+    assert_eq!(display.to_ascii(), chip8.display().to_ascii());
+    assert!(display.get_pixel(0, 0));
+}
+
+/// Polls until the server has as many players as expected
+fn wait_for_players(server: &mut InputServer, chip8: &mut Chip8, players: usize) {
+    while server.player_count() != players {
+        server.poll(chip8);
+    }
+}
+
+#[test]
+fn remote_players_press_keys() {
+    let mut server = InputServer::bind("127.0.0.1:0").unwrap();
+    let mut client = InputClient::connect(server.local_addr().unwrap()).unwrap();
+    let mut chip8 = Chip8::headless();
+    wait_for_players(&mut server, &mut chip8, 1);
+
+    client.press(0xA).unwrap();
+
+    while !chip8.is_key_pressed(0xA) {
+        server.poll(&mut chip8);
+    }
+
+    client.release(0xA).unwrap();
+
+    while chip8.is_key_pressed(0xA) {
+        server.poll(&mut chip8);
+    }
+}
+
+#[test]
+fn malformed_input_is_skipped_and_disconnects_release_keys() {
+    let mut server = InputServer::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let mut chip8 = Chip8::headless();
+    wait_for_players(&mut server, &mut chip8, 1);
+
+    // A key that doesn't exist, a state that doesn't exist and then a valid press
+    stream.write_all(&[0x10, 1, 0x3, 7, 0x3, 1]).unwrap();
+
+    while !chip8.is_key_pressed(0x3) {
+        server.poll(&mut chip8);
+    }
+
+    drop(stream);
+    wait_for_players(&mut server, &mut chip8, 0);
+
+    assert!(!chip8.is_key_pressed(0x3));
+}
@@ -0,0 +1,835 @@
+use chip8::{Chip8, Chip8Config, Chip8Error, QuirkPreset, Quirks, chip8::{FONT_BASE, FONT_LEN, PROGRAM_START}, instruction::supported_opcodes};
+use std::{cell::RefCell, rc::Rc};
+use winit::event::VirtualKeyCode;
+
+/// A headless machine with a fixed seed and the given opcodes loaded at the start of the program
+fn machine_with(opcodes: &[u16], quirks: Quirks) -> Chip8 {
+    let mut chip8 = Chip8::test_instance();
+    chip8.set_quirks(quirks);
+    load(&mut chip8, opcodes);
+
+    chip8
+}
+
+fn machine_from_config(opcodes: &[u16], config: Chip8Config) -> Chip8 {
+    let mut chip8 = Chip8::headless_from_config(config).unwrap();
+    load(&mut chip8, opcodes);
+
+    chip8
+}
+
+fn load(chip8: &mut Chip8, opcodes: &[u16]) {
+    let program = opcodes.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    chip8.load_program(program).unwrap();
+}
+
+/// Runs `cycles` instructions of the opcodes with the default quirks
+fn run(opcodes: &[u16], cycles: u32) -> Chip8 {
+    run_with_quirks(opcodes, cycles, Quirks::default())
+}
+
+fn run_with_quirks(opcodes: &[u16], cycles: u32, quirks: Quirks) -> Chip8 {
+    let mut chip8 = machine_with(opcodes, quirks);
+    assert_eq!(chip8.run_cycles(cycles).unwrap(), cycles, "the program stopped early");
+
+    chip8
+}
+
+/// Runs the opcodes until one of them fails
+fn run_until_error(opcodes: &[u16], cycles: u32) -> Chip8Error {
+    let mut chip8 = machine_with(opcodes, Quirks::default());
+
+    chip8.run_cycles(cycles).expect_err("the program didn't fail")
+}
+
+const BLANK: [[bool; 64]; 32] = [[false; 64]; 32];
+
+#[test]
+fn clear_screen() {
+    let chip8 = run(&[0xF029, 0xD005, 0x00E0], 3);
+
+    assert_eq!(chip8.display_diff(&BLANK), 0);
+}
+
+#[test]
+fn call_and_return() {
+    let chip8 = run(&[0x2204, 0x0000, 0x6101], 1);
+    assert_eq!(chip8.pc(), 0x204);
+    assert_eq!(chip8.stack(), &[0x202]);
+
+    let chip8 = run(&[0x2206, 0x6101, 0x1204, 0x00EE], 3);
+    assert_eq!(chip8.pc(), 0x204);
+    assert_eq!(chip8.registers()[1], 1);
+    assert!(chip8.stack().is_empty());
+}
+
+#[test]
+fn return_without_a_call_underflows() {
+    assert_eq!(run_until_error(&[0x00EE], 1), Chip8Error::StackUnderflow);
+}
+
+#[test]
+fn calls_past_the_stack_overflow() {
+    // Calls itself until the stack runs out
+    assert_eq!(run_until_error(&[0x2200], 100), Chip8Error::StackOverflow);
+}
+
+#[test]
+fn every_supported_opcode_executes() {
+    for info in supported_opcodes() {
+        let opcode = u16::from_str_radix(&info.pattern.replace(['X', 'Y', 'N'], "1"), 16).unwrap();
+        let mut chip8 = machine_with(&[opcode], Quirks::default());
+
+        // Other errors like returning without a call are fine, the instruction was still recognized
+        assert_ne!(chip8.run_cycles(1), Err(Chip8Error::UnknownOpcode(opcode)), "{}", info.pattern);
+    }
+}
+
+#[test]
+fn jump() {
+    let chip8 = run(&[0x1206], 1);
+
+    assert_eq!(chip8.pc(), 0x206);
+    assert!(!chip8.is_halted());
+}
+
+#[test]
+fn jump_to_itself_halts() {
+    let chip8 = run(&[0x6001, 0x1202], 2);
+
+    assert_eq!(chip8.pc(), 0x202);
+    assert!(chip8.is_halted());
+}
+
+#[test]
+fn only_keypad_keys_resume_a_halt() {
+    let mut chip8 = run(&[0x6001, 0x1202], 2);
+
+    chip8.on_key_down(&VirtualKeyCode::Space);
+    assert!(chip8.is_halted());
+
+    chip8.on_key_down(&VirtualKeyCode::Q);
+    assert!(!chip8.is_halted());
+}
+
+#[test]
+fn skip_if_equal_to_value() {
+    let chip8 = run(&[0x6005, 0x3005, 0x6101, 0x6202], 3);
+    assert_eq!(chip8.registers()[1], 0);
+    assert_eq!(chip8.registers()[2], 2);
+
+    let chip8 = run(&[0x6005, 0x3006, 0x6101], 3);
+    assert_eq!(chip8.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_not_equal_to_value() {
+    let chip8 = run(&[0x6005, 0x4006, 0x6101, 0x6202], 3);
+    assert_eq!(chip8.registers()[1], 0);
+    assert_eq!(chip8.registers()[2], 2);
+
+    let chip8 = run(&[0x6005, 0x4005, 0x6101], 3);
+    assert_eq!(chip8.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_registers_equal() {
+    let chip8 = run(&[0x6005, 0x6105, 0x5010, 0x6201, 0x6302], 4);
+    assert_eq!(chip8.registers()[2], 0);
+    assert_eq!(chip8.registers()[3], 2);
+
+    let chip8 = run(&[0x6005, 0x6106, 0x5010, 0x6201], 4);
+    assert_eq!(chip8.registers()[2], 1);
+}
+
+#[test]
+fn skip_if_registers_not_equal() {
+    let chip8 = run(&[0x6005, 0x6106, 0x9010, 0x6201, 0x6302], 4);
+    assert_eq!(chip8.registers()[2], 0);
+    assert_eq!(chip8.registers()[3], 2);
+
+    let chip8 = run(&[0x6005, 0x6105, 0x9010, 0x6201], 4);
+    assert_eq!(chip8.registers()[2], 1);
+}
+
+#[test]
+fn set_register() {
+    let chip8 = run(&[0x6A42], 1);
+
+    assert_eq!(chip8.registers()[0xA], 0x42);
+}
+
+#[test]
+fn add_value_wraps_without_touching_vf() {
+    let chip8 = run(&[0x60FF, 0x7002], 2);
+
+    assert_eq!(chip8.registers()[0], 0x01);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn copy_register() {
+    let chip8 = run(&[0x6142, 0x8010], 2);
+
+    assert_eq!(chip8.registers()[0], 0x42);
+}
+
+#[test]
+fn bitwise_operations() {
+    assert_eq!(run(&[0x600C, 0x610A, 0x8011], 3).registers()[0], 0x0E);
+    assert_eq!(run(&[0x600C, 0x610A, 0x8012], 3).registers()[0], 0x08);
+    assert_eq!(run(&[0x600C, 0x610A, 0x8013], 3).registers()[0], 0x06);
+}
+
+#[test]
+fn add_registers_sets_carry() {
+    let chip8 = run(&[0x60F0, 0x6120, 0x8014], 3);
+    assert_eq!(chip8.registers()[0], 0x10);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    let chip8 = run(&[0x6010, 0x6120, 0x8014], 3);
+    assert_eq!(chip8.registers()[0], 0x30);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn subtract_registers_sets_not_borrow() {
+    let chip8 = run(&[0x6030, 0x6110, 0x8015], 3);
+    assert_eq!(chip8.registers()[0], 0x20);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    let chip8 = run(&[0x6010, 0x6130, 0x8015], 3);
+    assert_eq!(chip8.registers()[0], 0xE0);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn reverse_subtract_registers_sets_not_borrow() {
+    let chip8 = run(&[0x6010, 0x6130, 0x8017], 3);
+    assert_eq!(chip8.registers()[0], 0x20);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    let chip8 = run(&[0x6030, 0x6110, 0x8017], 3);
+    assert_eq!(chip8.registers()[0], 0xE0);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn flag_wins_when_vf_is_the_destination() {
+    let chip8 = run(&[0x6FF0, 0x6120, 0x8F14], 3);
+
+    assert_eq!(chip8.registers()[0xF], 1);
+}
+
+#[test]
+fn shifts_copy_vy_by_default() {
+    let chip8 = run(&[0x6000, 0x6103, 0x8016], 3);
+    assert_eq!(chip8.registers()[0], 0x01);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    let chip8 = run(&[0x6000, 0x6181, 0x801E], 3);
+    assert_eq!(chip8.registers()[0], 0x02);
+    assert_eq!(chip8.registers()[0xF], 1);
+}
+
+#[test]
+fn shifts_in_place_without_the_quirk() {
+    let quirks = Quirks { shift_uses_vy: false, ..Quirks::default() };
+
+    let chip8 = run_with_quirks(&[0x6004, 0x61FF, 0x8016], 3, quirks);
+    assert_eq!(chip8.registers()[0], 0x02);
+    assert_eq!(chip8.registers()[0xF], 0);
+
+    let chip8 = run_with_quirks(&[0x6040, 0x61FF, 0x801E], 3, quirks);
+    assert_eq!(chip8.registers()[0], 0x80);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn shift_8126_with_and_without_the_quirk() {
+    // V1 = 0x10 and V2 = 0x03 so shifting either one gives a different result and shifted out bit
+    let program = [0x6110, 0x6203, 0x8126];
+
+    let chip8 = run(&program, 3);
+    assert_eq!(chip8.registers()[1], 0x01);
+    assert_eq!(chip8.registers()[2], 0x03);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    let quirks = Quirks { shift_uses_vy: false, ..Quirks::default() };
+    let chip8 = run_with_quirks(&program, 3, quirks);
+    assert_eq!(chip8.registers()[1], 0x08);
+    assert_eq!(chip8.registers()[2], 0x03);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn shift_flag_wins_when_vf_is_the_destination() {
+    let quirks = Quirks { shift_uses_vy: false, ..Quirks::default() };
+
+    assert_eq!(run_with_quirks(&[0x6F03, 0x8F06], 2, quirks).registers()[0xF], 1);
+    assert_eq!(run_with_quirks(&[0x6F40, 0x8F0E], 2, quirks).registers()[0xF], 0);
+}
+
+#[test]
+fn set_i() {
+    let chip8 = run(&[0xA123], 1);
+
+    assert_eq!(chip8.i_register(), 0x123);
+}
+
+#[test]
+fn jump_with_offset() {
+    let chip8 = run(&[0x6004, 0x6310, 0xB300], 3);
+    assert_eq!(chip8.pc(), 0x304);
+
+    let quirks = Quirks { jump_uses_vx: true, ..Quirks::default() };
+    let chip8 = run_with_quirks(&[0x6004, 0x6310, 0xB300], 3, quirks);
+    assert_eq!(chip8.pc(), 0x310);
+}
+
+#[test]
+fn random_is_masked_and_seeded() {
+    let first = run(&[0xC00F, 0xC1FF], 2);
+    let second = run(&[0xC00F, 0xC1FF], 2);
+
+    assert!(first.registers()[0] <= 0x0F);
+    assert_eq!(first.registers(), second.registers());
+}
+
+#[test]
+fn draw_sets_vf_on_collision() {
+    // The font's 0 is a 4x5 outline with 14 pixels on
+    let chip8 = run(&[0xF029, 0xD005], 2);
+    assert_eq!(chip8.display_diff(&BLANK), 14);
+    assert!(chip8.display().get_pixel(0, 0));
+    assert_eq!(chip8.registers()[0xF], 0);
+
+    let chip8 = run(&[0xF029, 0xD005, 0xD005], 3);
+    assert_eq!(chip8.display_diff(&BLANK), 0);
+    assert_eq!(chip8.registers()[0xF], 1);
+}
+
+#[test]
+fn draw_at_register_position() {
+    let chip8 = run(&[0x6008, 0x6104, 0xF029, 0xD015], 4);
+
+    assert!(chip8.display().get_pixel(8, 4));
+    assert!(!chip8.display().get_pixel(0, 0));
+}
+
+#[test]
+fn draw_wraps_or_clips_each_axis() {
+    for (wrap_x, wrap_y) in [(true, true), (true, false), (false, true), (false, false)] {
+        let quirks = Quirks { wrap_x, wrap_y, ..Quirks::default() };
+
+        // A 0 at (62, 30) straddles both the right and the bottom edge
+        let chip8 = run_with_quirks(&[0x603E, 0x611E, 0xF229, 0xD015], 4, quirks);
+        let display = chip8.display();
+
+        assert!(display.get_pixel(62, 30), "the part inside the screen is always drawn");
+        assert_eq!(display.get_pixel(0, 30), wrap_x, "wrap_x: {wrap_x}, wrap_y: {wrap_y}");
+        assert_eq!(display.get_pixel(62, 0), wrap_y, "wrap_x: {wrap_x}, wrap_y: {wrap_y}");
+        assert_eq!(display.get_pixel(1, 0), wrap_x && wrap_y, "wrap_x: {wrap_x}, wrap_y: {wrap_y}");
+    }
+}
+
+/// Draws a 0 at (8, 4) in the given resolution, scrolls it and returns where its top left corner ended up
+fn scrolled_corner(resolution: u16, scroll: u16, quirks: Quirks) -> (usize, usize) {
+    let chip8 = run_with_quirks(&[resolution, 0x6008, 0x6104, 0x6200, 0xF229, 0xD015, scroll], 7, quirks);
+    let display = chip8.display();
+
+    assert_eq!(display.width(), if resolution == 0x00FF {128} else {64});
+
+    // The 0's top row is fully on, so its corner is the first pixel that's on
+    let corner = (0..display.height())
+        .flat_map(|y| (0..display.width()).map(move |x| (x, y)))
+        .find(|&(x, y)| display.get_pixel(x, y));
+
+    corner.expect("the sprite scrolled off the screen")
+}
+
+#[test]
+fn scrolls_move_the_same_in_both_resolutions_by_default() {
+    for resolution in [0x00FE, 0x00FF] {
+        assert_eq!(scrolled_corner(resolution, 0x00C3, Quirks::default()), (8, 7));
+        assert_eq!(scrolled_corner(resolution, 0x00FB, Quirks::default()), (12, 4));
+        assert_eq!(scrolled_corner(resolution, 0x00FC, Quirks::default()), (4, 4));
+    }
+}
+
+#[test]
+fn lowres_scrolls_are_halved_with_the_superchip_preset() {
+    let quirks = Quirks::preset(QuirkPreset::SuperChip);
+
+    // Odd rows round up
+    assert_eq!(scrolled_corner(0x00FE, 0x00C3, quirks), (8, 6));
+    assert_eq!(scrolled_corner(0x00FE, 0x00C4, quirks), (8, 6));
+    assert_eq!(scrolled_corner(0x00FE, 0x00FB, quirks), (10, 4));
+    assert_eq!(scrolled_corner(0x00FE, 0x00FC, quirks), (6, 4));
+
+    assert_eq!(scrolled_corner(0x00FF, 0x00C3, quirks), (8, 7));
+    assert_eq!(scrolled_corner(0x00FF, 0x00FB, quirks), (12, 4));
+    assert_eq!(scrolled_corner(0x00FF, 0x00FC, quirks), (4, 4));
+}
+
+#[test]
+fn scrolled_out_pixels_are_gone() {
+    // A 0 at (60, 0) is pushed past by the right edge once scrolled right, and doesn't come back when scrolled left
+    let chip8 = run(&[0x603C, 0x6100, 0xF129, 0xD015, 0x00FB, 0x00FC], 6);
+
+    assert_eq!(chip8.display_diff(&BLANK), 0);
+
+    // Scrolling down by more than the height clears the screen
+    let chip8 = run(&[0xF029, 0xD005, 0x00CF, 0x00CF, 0x00CF], 5);
+    assert_eq!(chip8.display_diff(&BLANK), 0);
+}
+
+#[test]
+fn fetching_past_the_end_of_memory_fails() {
+    for pc in [0xFFF, 0x1000] {
+        let mut chip8 = Chip8::test_instance();
+        chip8.set_pc(pc);
+
+        assert_eq!(chip8.run_cycles(1), Err(Chip8Error::OutOfBoundsMemory(0x1000)));
+        assert_eq!(chip8.pc(), pc);
+    }
+}
+
+#[test]
+fn running_off_the_end_of_memory_wraps_with_pc_wrap() {
+    // Writes LD V0, 0x42 to the last two bytes of memory
+    let writes_last_instruction = [0x6060, 0x6142, 0xAFFE, 0xF155];
+
+    for pc_wrap in [false, true] {
+        let mut chip8 = machine_with(&writes_last_instruction, Quirks { pc_wrap, ..Quirks::default() });
+        chip8.run_cycles(4).unwrap();
+        chip8.set_pc(0xFFE);
+        chip8.run_cycles(1).unwrap();
+
+        assert_eq!(chip8.registers()[0], 0x42);
+
+        if pc_wrap {
+            assert_eq!(chip8.pc(), 0x000);
+        } else {
+            assert_eq!(chip8.run_cycles(1), Err(Chip8Error::OutOfBoundsMemory(0x1000)));
+        }
+    }
+
+    // An instruction on the last byte continues at the start of memory, 0x42 0x00 doesn't skip since V2 is 0
+    let mut chip8 = machine_with(&writes_last_instruction, Quirks { pc_wrap: true, ..Quirks::default() });
+    chip8.run_cycles(4).unwrap();
+    chip8.set_pc(0xFFF);
+    chip8.run_cycles(1).unwrap();
+
+    assert_eq!(chip8.pc(), 0x001);
+}
+
+#[test]
+fn draw_past_the_end_of_memory_fails() {
+    assert_eq!(run_until_error(&[0xAFFE, 0xD005], 2), Chip8Error::OutOfBoundsMemory(0x1000));
+}
+
+#[test]
+fn skip_if_key_pressed() {
+    let mut chip8 = machine_with(&[0x6005, 0xE09E, 0x6101, 0xE0A1, 0x6201], Quirks::default());
+    chip8.press_key(5);
+    chip8.run_cycles(4).unwrap();
+
+    assert_eq!(chip8.registers()[1], 0);
+    assert_eq!(chip8.registers()[2], 1);
+}
+
+#[test]
+fn skip_if_key_not_pressed() {
+    let chip8 = run(&[0x6005, 0xE09E, 0x6101, 0xE0A1, 0x6201], 4);
+
+    assert_eq!(chip8.registers()[1], 1);
+    assert_eq!(chip8.registers()[2], 0);
+}
+
+/// Runs `E09E` on key 0 followed by setting V1 and V2, returns whether it skipped setting V1
+fn skips_on_key_zero(mut chip8: Chip8) -> bool {
+    chip8.run_cycles(2).unwrap();
+
+    chip8.registers()[1] == 0
+}
+
+#[test]
+fn key_checks_see_the_keys_latched_at_the_last_tick() {
+    for latch_keys in [false, true] {
+        let machine = || machine_from_config(&[0xE09E, 0x6101, 0x6202], Chip8Config { latch_keys, ..Chip8Config::default() });
+
+        // Pressed during the frame, latching only sees it after the next tick
+        let mut chip8 = machine();
+        chip8.press_key(0);
+        assert_eq!(skips_on_key_zero(chip8), !latch_keys, "latch_keys: {latch_keys}");
+
+        // Down at the tick but released during the frame, latching still sees it down
+        let mut chip8 = machine();
+        chip8.press_key(0);
+        chip8.tick_timers();
+        chip8.release_key(0);
+        assert_eq!(skips_on_key_zero(chip8), latch_keys, "latch_keys: {latch_keys}");
+    }
+}
+
+#[test]
+fn wait_for_key_stores_the_released_key() {
+    let mut chip8 = machine_with(&[0xF30A, 0x6101], Quirks::default());
+    chip8.run_cycles(1).unwrap();
+    assert!(chip8.is_waiting_for_key());
+
+    chip8.run_cycles(5).unwrap();
+    assert_eq!(chip8.pc(), PROGRAM_START + 2, "execution continued while waiting");
+
+    chip8.press_key(7);
+    chip8.release_key(7);
+    chip8.run_cycles(1).unwrap();
+
+    assert!(!chip8.is_waiting_for_key());
+    assert_eq!(chip8.registers()[3], 7);
+}
+
+#[test]
+fn auto_speed_slows_down_games_waiting_on_the_delay_timer_and_settles() {
+    // Draws once per frame and spends the rest of it waiting for the delay timer to run out
+    let program = [0x6001, 0xF015, 0xF007, 0x3000, 0x1204, 0xD005, 0x1200];
+
+    for enabled in [false, true] {
+        let mut chip8 = machine_with(&program, Quirks::default());
+        chip8.enable_auto_speed(enabled);
+
+        for _ in 0..600 {
+            let cycles = chip8.cycles_per_frame();
+            chip8.run_cycles(cycles).unwrap();
+            chip8.tick_timers();
+        }
+
+        assert_eq!(chip8.is_auto_speed_settled(), enabled);
+
+        if enabled {
+            assert!((5..30).contains(&chip8.cycles_per_frame()), "{} cycles per frame", chip8.cycles_per_frame());
+        } else {
+            assert_eq!(chip8.cycles_per_frame(), Chip8Config::default().cycles_per_frame);
+        }
+    }
+}
+
+#[test]
+fn ips_pacing_averages_out_to_the_target() {
+    let mut chip8 = machine_with(&[0x7001, 0x1200], Quirks::default());
+    chip8.set_ips(700);
+
+    let mut executed = 0;
+
+    // 10 seconds
+    for _ in 0..600 {
+        assert!((11..=12).contains(&chip8.cycles_per_frame()), "{} cycles per frame", chip8.cycles_per_frame());
+
+        let cycles = chip8.cycles_per_frame();
+        executed += chip8.run_cycles(cycles).unwrap();
+        chip8.tick_timers();
+    }
+
+    assert_eq!(executed, 7000);
+
+    // A fixed speed stops the pacing
+    chip8.set_cycles_per_frame(30);
+    chip8.tick_timers();
+    assert_eq!((chip8.ips(), chip8.cycles_per_frame()), (None, 30));
+}
+
+#[test]
+fn ips_pacing_and_auto_speed_exclude_each_other() {
+    let mut chip8 = machine_with(&[0x7001, 0x1200], Quirks::default());
+    chip8.enable_auto_speed(true);
+    chip8.set_ips(700);
+
+    assert!(!chip8.is_auto_speed_enabled());
+
+    for _ in 0..600 {
+        let cycles = chip8.cycles_per_frame();
+        chip8.run_cycles(cycles).unwrap();
+        chip8.tick_timers();
+
+        assert_eq!(chip8.ips(), Some(700));
+        assert!((11..=12).contains(&chip8.cycles_per_frame()), "{} cycles per frame", chip8.cycles_per_frame());
+    }
+
+    chip8.enable_auto_speed(true);
+    assert!(chip8.is_auto_speed_enabled());
+    assert_eq!(chip8.ips(), None);
+}
+
+#[test]
+fn draws_per_frame_are_limited() {
+    // Counts draws in V1
+    let program = [0x7101, 0xD005, 0x1200];
+    let display_wait = Quirks { display_wait: true, ..Quirks::default() };
+
+    for (quirks, max_draws, draws_per_frame) in [(Quirks::default(), None, 20), (Quirks::default(), Some(3), 3), (display_wait, None, 1)] {
+        let mut chip8 = machine_with(&program, quirks);
+        chip8.set_max_draws_per_frame(max_draws);
+
+        for frame in 1..=4 {
+            chip8.run_cycles(60).unwrap();
+            assert_eq!(chip8.registers()[1], frame * draws_per_frame, "max_draws: {max_draws:?}, frame {frame}");
+            chip8.tick_timers();
+        }
+    }
+}
+
+#[test]
+fn timers() {
+    let chip8 = run(&[0x6020, 0xF015, 0xF107, 0xF018], 4);
+
+    assert_eq!(chip8.delay_timer(), 0x20);
+    assert_eq!(chip8.registers()[1], 0x20);
+    assert_eq!(chip8.sound_timer(), 0x20);
+}
+
+#[test]
+fn timers_set_by_the_host() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut chip8 = Chip8::test_instance();
+
+    let started = events.clone();
+    chip8.set_on_sound_start(move || started.borrow_mut().push("start"));
+    let stopped = events.clone();
+    chip8.set_on_sound_stop(move || stopped.borrow_mut().push("stop"));
+
+    chip8.set_delay_timer(3);
+    chip8.set_sound_timer(2);
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (3, 2));
+    assert_eq!(*events.borrow(), ["start"]);
+
+    // Setting it while it's already counting down doesn't start it again
+    chip8.set_sound_timer(2);
+    chip8.tick_timers();
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (2, 1));
+
+    chip8.tick_timers();
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (1, 0));
+    assert_eq!(*events.borrow(), ["start", "stop"]);
+
+    chip8.set_sound_timer(5);
+    chip8.set_sound_timer(0);
+    assert_eq!(*events.borrow(), ["start", "stop", "start", "stop"]);
+}
+
+#[test]
+fn frames_are_counted_until_reloading() {
+    let mut chip8 = machine_with(&[0x1200], Quirks::default());
+    assert_eq!(chip8.frame_count(), 0);
+
+    for _ in 0..120 {
+        chip8.tick_timers();
+    }
+
+    assert_eq!(chip8.frame_count(), 120);
+
+    chip8.reset_and_reload(vec![0x12, 0x00]).unwrap();
+    assert_eq!(chip8.frame_count(), 0);
+}
+
+#[test]
+fn add_to_i() {
+    let chip8 = run(&[0x6005, 0xA300, 0xF01E], 3);
+
+    assert_eq!(chip8.i_register(), 0x305);
+    assert_eq!(chip8.registers()[0xF], 0);
+}
+
+#[test]
+fn add_to_i_sets_vf_with_the_quirk() {
+    let quirks = Quirks { fx1e_sets_vf: true, ..Quirks::default() };
+
+    let chip8 = run_with_quirks(&[0x6FFF, 0x6001, 0xAFFE, 0xF01E], 4, quirks);
+    assert_eq!(chip8.i_register(), 0xFFF);
+    assert_eq!(chip8.registers()[0xF], 0);
+
+    let chip8 = run_with_quirks(&[0x6002, 0xAFFE, 0xF01E], 3, quirks);
+    assert_eq!(chip8.i_register(), 0x1000);
+    assert_eq!(chip8.registers()[0xF], 1);
+}
+
+#[test]
+fn add_to_i_leaves_vf_without_the_quirk() {
+    let chip8 = run(&[0x6F05, 0x6002, 0xAFFE, 0xF01E], 4);
+
+    assert_eq!(chip8.i_register(), 0x1000);
+    assert_eq!(chip8.registers()[0xF], 5);
+}
+
+#[test]
+fn font_character() {
+    let chip8 = run(&[0x600A, 0xF029], 2);
+    assert_eq!(chip8.i_register(), FONT_BASE + 0xA * 5);
+
+    // Only the low nibble picks the character
+    let chip8 = run(&[0x601A, 0xF029], 2);
+    assert_eq!(chip8.i_register(), FONT_BASE + 0xA * 5);
+}
+
+#[test]
+fn font_at_another_base() {
+    let mut chip8 = machine_from_config(&[0x600A, 0xF029], Chip8Config { font_base: 0x100, ..Chip8Config::default() });
+    chip8.run_cycles(2).unwrap();
+
+    assert_eq!(chip8.i_register(), 0x100 + 0xA * 5);
+    assert_eq!(chip8.memory()[0x100 + 0xA * 5..0x100 + 0xB * 5], [0xF0, 0x90, 0xF0, 0x90, 0x90]);
+    assert!(chip8.memory()[FONT_BASE..FONT_BASE + FONT_LEN].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn fonts_that_dont_fit_are_rejected() {
+    for font_base in [PROGRAM_START - FONT_LEN + 1, 0x1000] {
+        let result = Chip8::headless_from_config(Chip8Config { font_base, ..Chip8Config::default() });
+        assert_eq!(result.err(), Some(Chip8Error::FontOutOfRange(font_base)));
+    }
+
+    assert!(Chip8::headless_from_config(Chip8Config { font_base: PROGRAM_START - FONT_LEN, ..Chip8Config::default() }).is_ok());
+}
+
+#[test]
+fn binary_coded_decimal() {
+    let chip8 = run(&[0x60FE, 0xA300, 0xF033], 3);
+
+    assert_eq!(chip8.memory()[0x300..0x303], [2, 5, 4]);
+}
+
+#[test]
+fn binary_coded_decimal_of_poked_registers() {
+    let mut chip8 = machine_with(&[0x6000, 0xF133], Quirks::default());
+    // Skips the first instruction
+    chip8.set_pc(PROGRAM_START + 2);
+    chip8.set_register(1, 73);
+    chip8.set_i(0x300);
+
+    chip8.run_cycles(1).unwrap();
+
+    assert_eq!(chip8.memory()[0x300..0x303], [0, 7, 3]);
+    assert_eq!(chip8.pc(), PROGRAM_START + 4);
+}
+
+#[test]
+fn store_registers() {
+    let chip8 = run(&[0x6001, 0x6102, 0x6203, 0x6304, 0xA300, 0xF255], 6);
+
+    assert_eq!(chip8.memory()[0x300..0x304], [1, 2, 3, 0]);
+    assert_eq!(chip8.i_register(), 0x300);
+}
+
+#[test]
+fn load_registers() {
+    // Loads the 3 bytes after the program's self jump
+    let mut chip8 = Chip8::headless();
+    chip8.load_program(vec![0xA2, 0x06, 0xF2, 0x65, 0x12, 0x04, 0x11, 0x22, 0x33]).unwrap();
+    chip8.run_cycles(2).unwrap();
+
+    assert_eq!(chip8.registers()[..4], [0x11, 0x22, 0x33, 0]);
+    assert_eq!(chip8.i_register(), 0x206);
+}
+
+#[test]
+fn load_and_store_increment_i_with_the_quirk() {
+    let quirks = Quirks { load_store_increments_i: true, ..Quirks::default() };
+
+    let chip8 = run_with_quirks(&[0x6001, 0x6102, 0x6203, 0xA300, 0xF255], 5, quirks);
+    assert_eq!(chip8.memory()[0x300..0x303], [1, 2, 3]);
+    assert_eq!(chip8.i_register(), 0x303);
+
+    let chip8 = run_with_quirks(&[0xA300, 0xF165], 2, quirks);
+    assert_eq!(chip8.i_register(), 0x302);
+}
+
+#[test]
+fn store_past_the_end_of_memory_fails() {
+    assert_eq!(run_until_error(&[0xAFFE, 0xF255], 2), Chip8Error::OutOfBoundsMemory(0x1000));
+}
+
+#[test]
+fn unknown_opcode_fails() {
+    assert_eq!(run_until_error(&[0x5121], 1), Chip8Error::UnknownOpcode(0x5121));
+}
+
+#[test]
+fn custom_handler_handles_unknown_opcodes() {
+    let mut chip8 = machine_with(&[0x0123, 0x5121], Quirks::default());
+    chip8.set_custom_handler(|context, opcode| {
+        if opcode >> 12 != 0 {
+            return false;
+        }
+
+        context.registers[0] = (opcode & 0xFF) as u8;
+        true
+    });
+
+    assert_eq!(chip8.run_cycles(2), Err(Chip8Error::UnknownOpcode(0x5121)));
+    assert_eq!(chip8.registers()[0], 0x23);
+}
+
+#[test]
+fn the_program_range_covers_the_loaded_rom() {
+    let mut chip8 = Chip8::test_instance();
+    assert_eq!((chip8.program_len(), chip8.program_range()), (0, PROGRAM_START..PROGRAM_START));
+
+    chip8.load_program(vec![0xAB; 10]).unwrap();
+
+    assert_eq!(chip8.program_len(), 10);
+    assert_eq!(chip8.program_range(), 0x200..0x20A);
+    assert!(chip8.memory()[chip8.program_range()].iter().all(|&byte| byte == 0xAB));
+    assert_eq!(chip8.memory()[chip8.program_range().end], 0);
+}
+
+#[test]
+fn larger_memory_is_addressable() {
+    let mut chip8 = Chip8::headless_from_config(Chip8Config { ram_size: 0x10000, ..Chip8Config::default() }).unwrap();
+    chip8.load_program(vec![0xAF, 0xFF, 0x60, 0x10, 0xF0, 0x1E, 0xF0, 0x55]).unwrap();
+    chip8.run_cycles(4).unwrap();
+
+    assert_eq!(chip8.memory().len(), 0x10000);
+    assert_eq!(chip8.i_register(), 0x100F);
+    assert_eq!(chip8.memory()[0x100F], 0x10);
+
+    // Past what the 12 bits of ANNN reach, stores and loads through I work up to the last byte
+    let config = Chip8Config { ram_size: 0x10000, quirks: Quirks { load_store_increments_i: false, ..Quirks::default() }, ..Chip8Config::default() };
+    let mut chip8 = machine_from_config(&[0x60AB, 0xF055, 0x6000, 0xF065], config.clone());
+    chip8.set_i(0xF000);
+    chip8.run_cycles(4).unwrap();
+
+    assert_eq!(chip8.memory()[0xF000], 0xAB);
+    assert_eq!(chip8.registers()[0], 0xAB);
+
+    let mut chip8 = machine_from_config(&[0x6001, 0x6102, 0xF155], config);
+    chip8.set_i(0xFFFF);
+    assert_eq!(chip8.run_cycles(3), Err(Chip8Error::OutOfBoundsMemory(0x10000)));
+    assert_eq!(chip8.memory()[0xFFFF], 0);
+
+    assert_eq!(run_until_error(&[0xAFFF, 0x6010, 0xF01E, 0xF055], 4), Chip8Error::OutOfBoundsMemory(0x100F));
+}
+
+#[test]
+fn memory_too_small_for_a_program_is_rejected() {
+    for ram_size in [0, FONT_BASE, PROGRAM_START] {
+        let result = Chip8::headless_from_config(Chip8Config { ram_size, ..Chip8Config::default() });
+        assert_eq!(result.err(), Some(Chip8Error::RamTooSmall(ram_size)));
+    }
+
+    let mut chip8 = Chip8::headless_from_config(Chip8Config { ram_size: PROGRAM_START + 2, ..Chip8Config::default() }).unwrap();
+    assert_eq!(chip8.max_program_size(), 2);
+    assert_eq!(chip8.load_program(vec![0x00, 0xE0, 0x00]), Err(Chip8Error::RomTooLarge));
+}
+
+#[test]
+fn errors_pause_with_halt_on_error() {
+    let mut chip8 = Chip8::headless_from_config(Chip8Config { halt_on_error: true, ..Chip8Config::default() }).unwrap();
+    chip8.load_program(vec![0x61, 0x01, 0x00, 0xEE, 0x62, 0x01]).unwrap();
+
+    assert_eq!(chip8.run_cycles(10), Err(Chip8Error::StackUnderflow));
+    assert!(chip8.paused);
+    assert_eq!(chip8.registers()[1], 1, "the state before the error was lost");
+
+    assert_eq!(chip8.run_cycles(10), Ok(0));
+    assert_eq!(chip8.registers()[2], 0);
+}
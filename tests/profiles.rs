@@ -0,0 +1,59 @@
+use chip8::{Chip8, Quirks, profiles};
+
+#[test]
+fn the_ibm_logo_is_recognized_by_its_hash() {
+    let mut chip8 = Chip8::test_instance();
+    chip8.load_program(include_bytes!("../examples/ibm_logo.ch8").to_vec()).unwrap();
+
+    assert_eq!(chip8.apply_known_profile().as_deref(), Some("IBM Logo"));
+}
+
+#[test]
+fn games_are_recognized_by_their_title() {
+    for name in ["Blinky [Hans Christian Egeberg, 1991].ch8", "BLINKY.ch8", "blinky"] {
+        let mut chip8 = Chip8::test_instance();
+        chip8.load_program(vec![0x12, 0x00]).unwrap();
+        chip8.set_rom_name(name);
+
+        assert_eq!(chip8.apply_known_profile().as_deref(), Some("Blinky"), "{name}");
+        assert!(!chip8.quirks().shift_uses_vy && !chip8.quirks().load_store_increments_i);
+    }
+
+    let mut chip8 = Chip8::test_instance();
+    chip8.load_program(vec![0x12, 0x00]).unwrap();
+    chip8.set_rom_name("Blinky 2.ch8");
+
+    assert_eq!(chip8.apply_known_profile(), None);
+}
+
+#[test]
+fn profiles_load_from_json_and_take_priority() {
+    let json = r#"[
+        { "name": "Loop", "hash": null, "title": "Loop", "quirks": { "jump_uses_vx": true }, "cycles_per_frame": 15 },
+        { "name": "My IBM Logo", "hash": 7270027583838894241, "quirks": {}, "cycles_per_frame": 5 }
+    ]"#;
+    let user_profiles = profiles::from_json(json).unwrap();
+
+    assert_eq!(user_profiles[0].quirks, Quirks { jump_uses_vx: true, ..Quirks::default() });
+    assert_eq!((user_profiles[1].hash, user_profiles[1].title.as_deref()), (Some(0x64e45391ba0238a1), None));
+
+    let mut chip8 = Chip8::test_instance();
+    chip8.add_known_profiles(user_profiles);
+    chip8.load_program(include_bytes!("../examples/ibm_logo.ch8").to_vec()).unwrap();
+
+    assert_eq!(chip8.apply_known_profile().as_deref(), Some("My IBM Logo"));
+    assert_eq!(chip8.cycles_per_frame(), 5);
+
+    chip8.load_program(vec![0x12, 0x00]).unwrap();
+    chip8.set_rom_name("loop.ch8");
+    assert_eq!(chip8.apply_known_profile().as_deref(), Some("Loop"));
+    assert!(chip8.quirks().jump_uses_vx);
+}
+
+#[test]
+fn invalid_profile_files_are_reported() {
+    let error = profiles::from_file(std::path::Path::new("no such file.json")).unwrap_err();
+    assert!(error.starts_with("couldn't read no such file.json"), "{error}");
+
+    assert!(profiles::from_json("[{ \"name\": \"No quirks\" }]").is_err());
+}
@@ -0,0 +1,74 @@
+use chip8::{Chip8, replay::{InputEvent, InputLog}};
+
+/// Waits for a key and draws its hex digit next to the previous one, forever
+const DRAWS_PRESSED_KEYS: [u8; 10] = [
+    0xF0, 0x0A, // 200: LD V0, K
+    0xF0, 0x29, // 202: LD F, V0
+    0xD1, 0x25, // 204: DRW V1, V2, 5
+    0x71, 0x05, // 206: ADD V1, 0x05
+    0x12, 0x00  // 208: JP 0x200
+];
+
+/// Runs 30 frames, pressing each key on its frame and releasing it on the next one
+fn run_frames(chip8: &mut Chip8, presses: &[(u64, u8)]) {
+    for frame in 0..30 {
+        for &(press_frame, key) in presses {
+            if press_frame == frame {
+                chip8.press_key(key);
+            } else if press_frame + 1 == frame {
+                chip8.release_key(key);
+            }
+        }
+
+        chip8.run_cycles(chip8.cycles_per_frame()).unwrap();
+        chip8.tick_timers();
+    }
+}
+
+#[test]
+fn replaying_recorded_input_draws_the_same_screen() {
+    let mut chip8 = Chip8::test_instance();
+    chip8.load_program(DRAWS_PRESSED_KEYS.to_vec()).unwrap();
+    chip8.start_recording();
+    run_frames(&mut chip8, &[(2, 0xA), (10, 0x3), (11, 0x7)]);
+
+    let log = chip8.stop_recording();
+    assert_eq!(log.events.len(), 6);
+    assert_eq!(log.events[0], InputEvent { frame: 2, key: 0xA, pressed: true });
+
+    let mut replay = Chip8::test_instance();
+    replay.load_program(DRAWS_PRESSED_KEYS.to_vec()).unwrap();
+    replay.play_input_log(log);
+    run_frames(&mut replay, &[]);
+
+    assert!(!replay.is_playing_input());
+    assert_eq!(replay.registers()[1], 15, "three digits were drawn");
+    assert_eq!(replay.display().to_ascii(), chip8.display().to_ascii());
+}
+
+#[test]
+fn input_logs_round_trip_through_text() {
+    let log = InputLog {
+        events: vec![
+            InputEvent { frame: 0, key: 0x1, pressed: true },
+            InputEvent { frame: 120, key: 0xA, pressed: false }
+        ]
+    };
+
+    assert_eq!(log.to_string(), "0 1 down\n120 A up\n");
+    assert_eq!(log.to_string().parse(), Ok(log));
+    assert_eq!("\n0 1 down\n\n".parse::<InputLog>().map(|log| log.events.len()), Ok(1));
+}
+
+#[test]
+fn invalid_input_logs_are_rejected() {
+    let error = |text: &str| text.parse::<InputLog>().unwrap_err();
+
+    assert_eq!(error("1 A"), "line 1: expected a frame, a key and down or up");
+    assert_eq!(error("1 A down now"), "line 1: expected a frame, a key and down or up");
+    assert_eq!(error("-1 A down"), "line 1: invalid frame");
+    assert_eq!(error("1 10 down"), "line 1: the key must be a hex digit");
+    assert_eq!(error("1 G down"), "line 1: the key must be a hex digit");
+    // Blank lines still count
+    assert_eq!(error("1 A down\n\n1 A held"), "line 3: expected down or up");
+}